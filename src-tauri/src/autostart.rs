@@ -46,6 +46,19 @@ pub fn disable_autostart() -> Result<()> {
     Ok(())
 }
 
+/// 服务模式下的开机自启：注册为自动启动的 Windows 服务，而非 HKCU\\...\\Run 项。
+#[cfg(windows)]
+pub fn enable_service_autostart() -> Result<()> {
+    // 安装服务即为其设置 AutoStart 启动类型
+    crate::service::install_service()
+}
+
+/// 注销服务模式的开机自启。
+#[cfg(windows)]
+pub fn disable_service_autostart() -> Result<()> {
+    crate::service::uninstall_service()
+}
+
 #[allow(dead_code)]
 pub fn is_autostart_enabled() -> Result<bool> {
     let current_exe = env::current_exe()
@@ -2,15 +2,40 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use dirs;
 use anyhow::{Result, anyhow};
-use log::info;
+use log::{info, warn};
 use std::fs;
 
+/// 系统代理的工作模式：关闭、固定 `host:port`、或 PAC 脚本 URL。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    Off,
+    Fixed,
+    Pac,
+}
+
+impl Default for ProxyMode {
+    fn default() -> Self {
+        ProxyMode::Off
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LastState {
     pub was_running: bool,
     pub system_proxy_enabled: bool,
     pub auto_start_checked: bool,
     pub preferred_mode: i32, // 0=自动检测, 1=内嵌模式(代码没有写), 2=外部模式
+    /// 上次使用的系统代理模式，用于启动时恢复。
+    #[serde(default)]
+    pub proxy_mode: ProxyMode,
+    /// 上次使用的 PAC 脚本 URL（`proxy_mode == Pac` 时有效）。
+    #[serde(default)]
+    pub pac_url: String,
+    /// 本应用接管系统代理前的原始配置快照；`None` 表示当前未接管。
+    /// 关闭代理时据此原样还原，避免清空用户/企业环境原有的设置。
+    #[serde(default)]
+    pub original_proxy: Option<crate::sysproxy::ProxySnapshot>,
 }
 
 impl Default for LastState {
@@ -20,10 +45,28 @@ impl Default for LastState {
             system_proxy_enabled: false,
             auto_start_checked: false,
             preferred_mode: 0,
+            proxy_mode: ProxyMode::Off,
+            pac_url: String::new(),
+            original_proxy: None,
         }
     }
 }
 
+/// 单个服务器的自定义启动配置（参考 odproxy 的 `SpawnConf`）：
+/// 覆盖可执行文件路径、追加额外命令行参数、注入环境变量。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnConf {
+    /// 可执行文件路径覆盖；为空时回退到默认的 ech-workers 二进制。
+    #[serde(default)]
+    pub command: Option<String>,
+    /// 追加到默认参数之后的额外命令行参数。
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 启动子进程时注入的环境变量。
+    #[serde(default)]
+    pub envs: Vec<(String, String)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Server {
     pub id: String,
@@ -35,25 +78,97 @@ pub struct Server {
     pub dns: String,
     pub ech: String,
     pub routing_mode: String,
+    #[serde(default)]
+    pub spawn: Option<SpawnConf>,
+    /// 上游（父级）代理 URL，空字符串表示不使用上游代理。
+    #[serde(default)]
+    pub upstream_proxy: String,
+    #[serde(default)]
+    pub upstream_proxy_user: String,
+    #[serde(default)]
+    pub upstream_proxy_pass: String,
+    /// 进程意外退出时是否自动重启。
+    #[serde(default)]
+    pub auto_restart: bool,
+    /// 自动重启的最大次数。
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    /// 就绪探测超时（秒）：start 需在此时间内连上监听端口才算成功。
+    #[serde(default = "default_readiness_timeout_secs")]
+    pub readiness_timeout_secs: u64,
+    /// 空闲自动停止超时（秒）；`None` 表示不启用。
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+    /// 系统代理模式：固定端点或 PAC 脚本 URL。
+    #[serde(default)]
+    pub proxy_mode: ProxyMode,
+    /// PAC 脚本 URL（`proxy_mode == Pac` 时有效）。
+    #[serde(default)]
+    pub pac_url: String,
+    /// 系统代理绕过列表（以 `;` 分隔的主机/通配符），这些主机直连不走代理。
+    #[serde(default)]
+    pub bypass: String,
+    /// 是否自动绕过本地/内网地址（localhost、127.*、10.*、192.168.* 等）。
+    #[serde(default)]
+    pub bypass_local: bool,
 }
 
+/// 当前配置文件的结构版本号。新增结构字段时递增，并在 `migrate_file_model` 中处理升级。
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:30000";
+const DEFAULT_SERVER_ADDR: &str = "example.workers.dev:443";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileModel {
+    #[serde(default)]
+    pub schema_version: u32,
     pub servers: Vec<Server>,
     pub current_server_id: String,
     pub last_state: LastState,
+    /// 是否显示代理状态变更的桌面通知。
+    #[serde(default = "default_notifications")]
+    pub notifications: bool,
+}
+
+fn default_notifications() -> bool {
+    true
 }
 
 impl Default for FileModel {
     fn default() -> Self {
         Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
             servers: Vec::new(),
             current_server_id: String::new(),
             last_state: LastState::default(),
+            notifications: default_notifications(),
         }
     }
 }
 
+/// 将 `host:port` 形式的地址解析为 (host, port)，非法返回 `None`。
+fn parse_host_port(addr: &str) -> Option<(&str, u16)> {
+    let (host, port) = addr.rsplit_once(':')?;
+    if host.is_empty() {
+        return None;
+    }
+    let port: u16 = port.parse().ok()?;
+    Some((host, port))
+}
+
+/// 就地升级旧版本的 `FileModel`，使其补齐新增字段而非在解析失败时重置为默认值。
+fn migrate_file_model(model: &mut FileModel) {
+    if model.schema_version < CURRENT_SCHEMA_VERSION {
+        info!(
+            "Migrating config schema from v{} to v{}",
+            model.schema_version, CURRENT_SCHEMA_VERSION
+        );
+        // v0 -> v1：新增 spawn / upstream_proxy 字段，serde 默认值已补齐，仅提升版本号
+        model.schema_version = CURRENT_SCHEMA_VERSION;
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyConfig {
     pub listen_addr: String,
@@ -63,6 +178,66 @@ pub struct ProxyConfig {
     pub dns_server: String,
     pub ech_domain: String,
     pub routing_mode: String,
+    #[serde(default)]
+    pub spawn: Option<SpawnConf>,
+    #[serde(default)]
+    pub upstream_proxy: String,
+    #[serde(default)]
+    pub upstream_proxy_user: String,
+    #[serde(default)]
+    pub upstream_proxy_pass: String,
+    /// 进程意外退出时是否自动重启。
+    #[serde(default)]
+    pub auto_restart: bool,
+    /// 自动重启的最大次数。
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    /// 就绪探测超时（秒）：start 需在此时间内连上监听端口才算成功。
+    #[serde(default = "default_readiness_timeout_secs")]
+    pub readiness_timeout_secs: u64,
+    /// 空闲自动停止超时（秒）；`None` 表示不启用。
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+    /// 系统代理模式：固定端点或 PAC 脚本 URL。
+    #[serde(default)]
+    pub proxy_mode: ProxyMode,
+    /// PAC 脚本 URL（`proxy_mode == Pac` 时有效）。
+    #[serde(default)]
+    pub pac_url: String,
+    /// 系统代理绕过列表（以 `;` 分隔的主机/通配符），这些主机直连不走代理。
+    #[serde(default)]
+    pub bypass: String,
+    /// 是否自动绕过本地/内网地址。
+    #[serde(default)]
+    pub bypass_local: bool,
+}
+
+/// 本地/内网地址的标准绕过集合，勾选“绕过本地地址”时追加到用户列表之后。
+pub const LOCAL_BYPASS: &str = "localhost;127.*;10.*;172.16.*-172.31.*;192.168.*;<local>";
+
+/// 将用户自定义绕过列表与可选的本地地址快捷集合合并为一个 `;` 分隔的字符串。
+pub fn effective_bypass(bypass: &str, bypass_local: bool) -> String {
+    let mut parts: Vec<&str> = bypass
+        .split(';')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if bypass_local {
+        for p in LOCAL_BYPASS.split(';') {
+            if !parts.contains(&p) {
+                parts.push(p);
+            }
+        }
+    }
+    parts.join(";")
+}
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
+fn default_readiness_timeout_secs() -> u64 {
+    5
 }
 
 impl Default for ProxyConfig {
@@ -75,6 +250,18 @@ impl Default for ProxyConfig {
             dns_server: "dns.alidns.com/dns-query".to_string(),
             ech_domain: "cloudflare-ech.com".to_string(),
             routing_mode: "global".to_string(),
+            spawn: None,
+            upstream_proxy: String::new(),
+            upstream_proxy_user: String::new(),
+            upstream_proxy_pass: String::new(),
+            auto_restart: false,
+            max_restarts: default_max_restarts(),
+            readiness_timeout_secs: default_readiness_timeout_secs(),
+            idle_timeout_secs: None,
+            proxy_mode: ProxyMode::default(),
+            pac_url: String::new(),
+            bypass: String::new(),
+            bypass_local: false,
         }
     }
 }
@@ -116,7 +303,11 @@ impl Config {
             
             config.model = serde_json::from_str(&content)
                 .map_err(|e| anyhow!("Failed to parse config file: {}", e))?;
-            
+
+            // 结构迁移 + 合法性校验，避免非法配置静默破坏代理
+            migrate_file_model(&mut config.model);
+            config.guard();
+
             info!("Configuration loaded from {:?}", config.config_file);
         } else {
             info!("Config file not found, using defaults");
@@ -127,9 +318,107 @@ impl Config {
         // 确保至少有一个服务器配置
         config.ensure_default_server()?;
 
+        // 未显式配置上游代理的服务器，尝试从 http_proxy/https_proxy 环境变量填充默认值
+        config.apply_env_upstream_proxy();
+
         Ok(config)
     }
 
+    /// 重新从磁盘读取 `config.json` 并替换内存中的模型，用于手动编辑配置文件后热加载。
+    pub fn reload(&mut self) -> Result<()> {
+        if !self.config_file.exists() {
+            return Err(anyhow!("Config file does not exist: {:?}", self.config_file));
+        }
+
+        let content = fs::read_to_string(&self.config_file)
+            .map_err(|e| anyhow!("Failed to read config file: {}", e))?;
+
+        self.model = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse config file: {}", e))?;
+
+        // 与 load() 一致：热加载也要做结构迁移与合法性校验，否则手动编辑引入的
+        // 非法 listen_addr / 重复 ID 会被原样接受并破坏代理
+        migrate_file_model(&mut self.model);
+        self.guard();
+
+        self.ensure_default_server()?;
+
+        info!("Configuration reloaded from {:?}", self.config_file);
+        Ok(())
+    }
+
+    /// 返回配置文件的绝对路径，供文件监视器注册使用。
+    pub fn config_file_path(&self) -> PathBuf {
+        self.config_file.clone()
+    }
+
+    /// 归一化并校验反序列化得到的模型（参考 clash-nyanpasu 的 `IClashTemp::guard`）：
+    /// 修正非法的监听/服务端地址、去除重复的服务器 ID、保证 `current_server_id` 指向存在的服务器，
+    /// 并在本地监听端口已被占用时自动顺延端口。
+    fn guard(&mut self) {
+        let mut seen_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+        for server in self.model.servers.iter_mut() {
+            // 监听地址必须是合法的 host:port，否则回退默认值
+            if parse_host_port(&server.listen).is_none() {
+                warn!("Invalid listen address {:?}, resetting to default", server.listen);
+                server.listen = DEFAULT_LISTEN_ADDR.to_string();
+            }
+            // 服务端地址同样要求 host:port
+            if parse_host_port(&server.server).is_none() {
+                warn!("Invalid server address {:?}, resetting to default", server.server);
+                server.server = DEFAULT_SERVER_ADDR.to_string();
+            }
+            // 重复或为空的 ID 重新生成
+            if server.id.is_empty() || !seen_ids.insert(server.id.clone()) {
+                let new_id = generate_server_id();
+                warn!("Duplicate server id {:?}, regenerated as {}", server.id, new_id);
+                server.id = new_id.clone();
+                seen_ids.insert(new_id);
+            }
+        }
+
+        // 监听端口若已被占用则顺延，避免启动即失败。但若占用者正是本应用自己的
+        // worker（上次会话遗留或仍在运行），顺延会让随后的系统代理自动恢复指向一个
+        // 无人监听的端口，反而把流量导向死端口，因此此时保持配置地址不变。
+        for server in self.model.servers.iter_mut() {
+            if crate::external_proxy::is_process_running(&server_binary_name(server)) {
+                continue;
+            }
+            if let Some(fixed) = bump_listen_addr_if_bound(&server.listen) {
+                warn!("Listen port {} busy, bumped to {}", server.listen, fixed);
+                server.listen = fixed;
+            }
+        }
+
+        // current_server_id 必须指向存在的服务器
+        let exists = self
+            .model
+            .servers
+            .iter()
+            .any(|s| s.id == self.model.current_server_id);
+        if !exists {
+            self.model.current_server_id = self
+                .model
+                .servers
+                .first()
+                .map(|s| s.id.clone())
+                .unwrap_or_default();
+        }
+    }
+
+    /// 对未设置 `upstream_proxy` 的服务器，从 `http_proxy`/`https_proxy` 环境变量填充默认值。
+    fn apply_env_upstream_proxy(&mut self) {
+        let env_proxy = default_upstream_proxy_from_env();
+        if let Some(env_proxy) = env_proxy {
+            for server in self.model.servers.iter_mut() {
+                if server.upstream_proxy.trim().is_empty() {
+                    server.upstream_proxy = env_proxy.clone();
+                }
+            }
+        }
+    }
+
     fn ensure_default_server(&mut self) -> Result<()> {
         if self.model.servers.is_empty() {
             let default_server = Server {
@@ -142,8 +431,20 @@ impl Config {
                 dns: "dns.alidns.com/dns-query".to_string(),
                 ech: "cloudflare-ech.com".to_string(),
                 routing_mode: "global".to_string(),
+                spawn: None,
+                upstream_proxy: String::new(),
+                upstream_proxy_user: String::new(),
+                upstream_proxy_pass: String::new(),
+                auto_restart: false,
+                max_restarts: default_max_restarts(),
+                readiness_timeout_secs: default_readiness_timeout_secs(),
+                idle_timeout_secs: None,
+                proxy_mode: ProxyMode::default(),
+                pac_url: String::new(),
+                bypass: String::new(),
+                bypass_local: false,
             };
-            
+
             self.model.servers.push(default_server);
             self.model.current_server_id = self.model.servers[0].id.clone();
             
@@ -176,12 +477,51 @@ impl Config {
                 dns_server: server.dns.clone(),
                 ech_domain: server.ech.clone(),
                 routing_mode: server.routing_mode.clone(),
+                spawn: server.spawn.clone(),
+                upstream_proxy: normalize_proxy_url(&server.upstream_proxy).unwrap_or_default(),
+                upstream_proxy_user: server.upstream_proxy_user.clone(),
+                upstream_proxy_pass: server.upstream_proxy_pass.clone(),
+                auto_restart: server.auto_restart,
+                max_restarts: server.max_restarts,
+                readiness_timeout_secs: server.readiness_timeout_secs,
+                idle_timeout_secs: server.idle_timeout_secs,
+                proxy_mode: server.proxy_mode,
+                pac_url: server.pac_url.clone(),
+                bypass: server.bypass.clone(),
+                bypass_local: server.bypass_local,
             }
         } else {
             ProxyConfig::default()
         }
     }
 
+    /// 为指定 id 的服务器构建 `ProxyConfig`，供多档案注册表按档案启动各自的代理。
+    /// 找不到该服务器时返回 `None`。
+    pub fn get_proxy_config_for(&self, id: &str) -> Option<ProxyConfig> {
+        let server = self.model.servers.iter().find(|s| s.id == id)?;
+        Some(ProxyConfig {
+            listen_addr: server.listen.clone(),
+            server_addr: server.server.clone(),
+            server_ip: server.ip.clone(),
+            token: server.token.clone(),
+            dns_server: server.dns.clone(),
+            ech_domain: server.ech.clone(),
+            routing_mode: server.routing_mode.clone(),
+            spawn: server.spawn.clone(),
+            upstream_proxy: normalize_proxy_url(&server.upstream_proxy).unwrap_or_default(),
+            upstream_proxy_user: server.upstream_proxy_user.clone(),
+            upstream_proxy_pass: server.upstream_proxy_pass.clone(),
+            auto_restart: server.auto_restart,
+            max_restarts: server.max_restarts,
+            readiness_timeout_secs: server.readiness_timeout_secs,
+            idle_timeout_secs: server.idle_timeout_secs,
+            proxy_mode: server.proxy_mode,
+            pac_url: server.pac_url.clone(),
+            bypass: server.bypass.clone(),
+            bypass_local: server.bypass_local,
+        })
+    }
+
     pub fn set_proxy_config(&mut self, config: ProxyConfig) {
         if let Some(mut server) = self.get_current_server() {
             server.listen = config.listen_addr;
@@ -191,7 +531,19 @@ impl Config {
             server.dns = config.dns_server;
             server.ech = config.ech_domain;
             server.routing_mode = config.routing_mode;
-            
+            server.spawn = config.spawn;
+            server.upstream_proxy = config.upstream_proxy;
+            server.upstream_proxy_user = config.upstream_proxy_user;
+            server.upstream_proxy_pass = config.upstream_proxy_pass;
+            server.auto_restart = config.auto_restart;
+            server.max_restarts = config.max_restarts;
+            server.readiness_timeout_secs = config.readiness_timeout_secs;
+            server.idle_timeout_secs = config.idle_timeout_secs;
+            server.proxy_mode = config.proxy_mode;
+            server.pac_url = config.pac_url;
+            server.bypass = config.bypass;
+            server.bypass_local = config.bypass_local;
+
             self.upsert_server(server);
         }
     }
@@ -240,6 +592,38 @@ impl Config {
         }
     }
 
+    /// 将订阅/分享链接解码得到的条目合并进服务器列表，按 `server`+`listen` 去重并分配新 ID，
+    /// 返回实际新增的服务器数量。
+    pub fn import_servers(&mut self, payload: &str) -> usize {
+        let servers = parse_subscription(payload);
+        let mut added = 0;
+        for mut server in servers {
+            let duplicated = self
+                .model
+                .servers
+                .iter()
+                .any(|s| s.server == server.server && s.listen == server.listen);
+            if duplicated {
+                continue;
+            }
+            server.id = generate_server_id();
+            self.model.servers.push(server);
+            added += 1;
+        }
+        if self.model.current_server_id.is_empty() {
+            if let Some(first) = self.model.servers.first() {
+                self.model.current_server_id = first.id.clone();
+            }
+        }
+        info!("Imported {} server(s) from subscription", added);
+        added
+    }
+
+    /// 是否启用桌面通知。
+    pub fn notifications_enabled(&self) -> bool {
+        self.model.notifications
+    }
+
     pub fn get_last_state(&self) -> LastState {
         self.model.last_state.clone()
     }
@@ -257,6 +641,166 @@ fn get_config_dir() -> Result<PathBuf> {
     Ok(config_dir)
 }
 
+/// 归一化上游代理 URL：空字符串视为“无代理”返回 `None`；缺少 `://` 时补全为 `http://`。
+pub fn normalize_proxy_url(url: &str) -> Option<String> {
+    let trimmed = url.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if trimmed.contains("://") {
+        Some(trimmed.to_string())
+    } else {
+        Some(format!("http://{}", trimmed))
+    }
+}
+
+/// 某服务器实际使用的外部二进制文件名（`spawn.command` 覆盖时取其文件名，否则用默认名），
+/// 用于判断占用监听端口的是否为本应用自己的 worker。
+fn server_binary_name(server: &Server) -> String {
+    server
+        .spawn
+        .as_ref()
+        .and_then(|s| s.command.as_ref())
+        .filter(|c| !c.is_empty())
+        .and_then(|c| std::path::Path::new(c).file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| crate::external_proxy::default_binary_name().to_string())
+}
+
+/// 若 `addr` 的本地监听端口已被占用，返回顺延到下一个可用端口的新地址；否则返回 `None`。
+/// 最多尝试若干次，全部失败则保持原样（由后续启动流程报错）。
+fn bump_listen_addr_if_bound(addr: &str) -> Option<String> {
+    use std::net::TcpListener;
+
+    let (host, mut port) = parse_host_port(addr)?;
+    // 端口可绑定说明空闲，无需顺延
+    if TcpListener::bind(addr).is_ok() {
+        return None;
+    }
+    for _ in 0..16 {
+        port = port.checked_add(1)?;
+        let candidate = format!("{}:{}", host, port);
+        if TcpListener::bind(&candidate).is_ok() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// 从 `http_proxy`/`https_proxy` 环境变量读取并归一化默认上游代理。
+pub fn default_upstream_proxy_from_env() -> Option<String> {
+    std::env::var("http_proxy")
+        .or_else(|_| std::env::var("HTTP_PROXY"))
+        .or_else(|_| std::env::var("https_proxy"))
+        .or_else(|_| std::env::var("HTTPS_PROXY"))
+        .ok()
+        .and_then(|v| normalize_proxy_url(&v))
+}
+
+/// 解析订阅内容：整体可能是 base64 编码，解码后按行分割，每行解析为一个 `Server`。
+/// 支持 `ech://` 分享链接与裸 `host:port` 两种形式，无法解析的行被跳过。
+pub fn parse_subscription(payload: &str) -> Vec<Server> {
+    use base64::Engine;
+
+    let trimmed = payload.trim();
+    // 订阅通常整体做 base64（含 URL-safe 变体），解码失败则按明文处理
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(trimmed)
+        .or_else(|_| base64::engine::general_purpose::URL_SAFE.decode(trimmed))
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+    let text = decoded.unwrap_or_else(|| trimmed.to_string());
+
+    text.lines()
+        .filter_map(|line| parse_share_entry(line.trim()))
+        .collect()
+}
+
+/// 解析单条分享条目：`ech://token@host:port?ip=..&dns=..&ech=..&routing=..#name`
+/// 或裸 `host:port`。解析失败返回 `None`。
+fn parse_share_entry(entry: &str) -> Option<Server> {
+    if entry.is_empty() {
+        return None;
+    }
+
+    let mut server = Server {
+        id: generate_server_id(),
+        name: String::new(),
+        server: String::new(),
+        listen: DEFAULT_LISTEN_ADDR.to_string(),
+        token: String::new(),
+        ip: String::new(),
+        dns: "dns.alidns.com/dns-query".to_string(),
+        ech: "cloudflare-ech.com".to_string(),
+        routing_mode: "global".to_string(),
+        spawn: None,
+        upstream_proxy: String::new(),
+        upstream_proxy_user: String::new(),
+        upstream_proxy_pass: String::new(),
+        auto_restart: false,
+        max_restarts: default_max_restarts(),
+        readiness_timeout_secs: default_readiness_timeout_secs(),
+        idle_timeout_secs: None,
+        proxy_mode: ProxyMode::default(),
+        pac_url: String::new(),
+        bypass: String::new(),
+        bypass_local: false,
+    };
+
+    if let Some(rest) = entry.strip_prefix("ech://") {
+        // 片段 #name
+        let (rest, name) = match rest.split_once('#') {
+            Some((r, n)) => (r, Some(n)),
+            None => (rest, None),
+        };
+        // 查询参数 ?k=v&...
+        let (authority, query) = match rest.split_once('?') {
+            Some((a, q)) => (a, Some(q)),
+            None => (rest, None),
+        };
+        // token@host:port
+        let host_port = match authority.split_once('@') {
+            Some((token, hp)) => {
+                server.token = token.to_string();
+                hp
+            }
+            None => authority,
+        };
+        if parse_host_port(host_port).is_none() {
+            return None;
+        }
+        server.server = host_port.to_string();
+
+        if let Some(query) = query {
+            for pair in query.split('&') {
+                if let Some((k, v)) = pair.split_once('=') {
+                    match k {
+                        "ip" => server.ip = v.to_string(),
+                        "dns" => server.dns = v.to_string(),
+                        "ech" => server.ech = v.to_string(),
+                        "routing" => server.routing_mode = v.to_string(),
+                        "listen" => server.listen = v.to_string(),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        server.name = name
+            .map(|n| n.to_string())
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| server.server.clone());
+    } else {
+        // 裸 host:port
+        if parse_host_port(entry).is_none() {
+            return None;
+        }
+        server.server = entry.to_string();
+        server.name = entry.to_string();
+    }
+
+    Some(server)
+}
+
 #[allow(dead_code)]
 pub fn generate_server_id() -> String {
     use rand::Rng;
@@ -264,23 +808,140 @@ pub fn generate_server_id() -> String {
     format!("server_{:x}", rng.gen::<u32>())
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-
-//     #[test]
-//     fn test_generate_server_id() {
-//         let id1 = generate_server_id();
-//         let id2 = generate_server_id();
-//         assert_ne!(id1, id2);
-//         assert!(id1.starts_with("server_"));
-//     }
-
-//     #[test]
-//     fn test_config_default() {
-//         let config = ProxyConfig::default();
-//         assert_eq!(config.listen_addr, "127.0.0.1:8080");
-//         assert_eq!(config.routing_mode, "global");
-//         assert_eq!(config.dns_server, "dns.alidns.com/dns-query");
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    fn make_server(id: &str, server: &str, listen: &str) -> Server {
+        Server {
+            id: id.to_string(),
+            name: String::new(),
+            server: server.to_string(),
+            listen: listen.to_string(),
+            token: String::new(),
+            ip: String::new(),
+            dns: String::new(),
+            ech: String::new(),
+            routing_mode: "global".to_string(),
+            spawn: None,
+            upstream_proxy: String::new(),
+            upstream_proxy_user: String::new(),
+            upstream_proxy_pass: String::new(),
+            auto_restart: false,
+            max_restarts: default_max_restarts(),
+            readiness_timeout_secs: default_readiness_timeout_secs(),
+            idle_timeout_secs: None,
+            proxy_mode: ProxyMode::default(),
+            pac_url: String::new(),
+            bypass: String::new(),
+            bypass_local: false,
+        }
+    }
+
+    fn config_with(servers: Vec<Server>, current: &str) -> Config {
+        Config {
+            config_dir: PathBuf::from("."),
+            config_file: PathBuf::from("./config.json"),
+            model: FileModel {
+                servers,
+                current_server_id: current.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn effective_bypass_merges_and_dedupes() {
+        // 不启用本地集合时原样保留（去空白）
+        assert_eq!(effective_bypass("a.com; b.com", false), "a.com;b.com");
+        // 启用本地集合时合并 LOCAL_BYPASS
+        let merged = effective_bypass("a.com", true);
+        assert!(merged.starts_with("a.com;"));
+        assert!(merged.contains("localhost"));
+        assert!(merged.contains("<local>"));
+        // 已存在的条目不重复追加
+        let deduped = effective_bypass("localhost", true);
+        assert_eq!(deduped.matches("localhost").count(), 1);
+    }
+
+    #[test]
+    fn normalize_proxy_url_adds_scheme() {
+        assert_eq!(normalize_proxy_url("1.2.3.4:8080").as_deref(), Some("http://1.2.3.4:8080"));
+        assert_eq!(
+            normalize_proxy_url("socks5://1.2.3.4:1080").as_deref(),
+            Some("socks5://1.2.3.4:1080")
+        );
+        assert_eq!(normalize_proxy_url("   "), None);
+    }
+
+    #[test]
+    fn parse_share_entry_reads_ech_link() {
+        let server =
+            parse_share_entry("ech://tok@host.example:443?ip=1.2.3.4&dns=d.q&routing=rule#MyNode")
+                .expect("ech link should parse");
+        assert_eq!(server.server, "host.example:443");
+        assert_eq!(server.token, "tok");
+        assert_eq!(server.ip, "1.2.3.4");
+        assert_eq!(server.dns, "d.q");
+        assert_eq!(server.routing_mode, "rule");
+        assert_eq!(server.name, "MyNode");
+    }
+
+    #[test]
+    fn parse_share_entry_reads_bare_host_port() {
+        let server = parse_share_entry("127.0.0.1:8443").expect("bare host:port should parse");
+        assert_eq!(server.server, "127.0.0.1:8443");
+        assert_eq!(server.name, "127.0.0.1:8443");
+        // 非法条目被拒绝
+        assert!(parse_share_entry("not-an-address").is_none());
+        assert!(parse_share_entry("").is_none());
+    }
+
+    #[test]
+    fn parse_subscription_decodes_base64_lines() {
+        let raw = "ech://tok@host.example:443#A\n2.2.2.2:9000";
+        let encoded = base64::engine::general_purpose::STANDARD.encode(raw);
+        let servers = parse_subscription(&encoded);
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].server, "host.example:443");
+        assert_eq!(servers[1].server, "2.2.2.2:9000");
+    }
+
+    #[test]
+    fn parse_subscription_accepts_plaintext() {
+        // 非 base64 内容按明文逐行解析，无法识别的行被跳过
+        let servers = parse_subscription("1.1.1.1:443\nnonsense\n2.2.2.2:444");
+        assert_eq!(servers.len(), 2);
+    }
+
+    #[test]
+    fn migrate_file_model_bumps_schema_version() {
+        let mut model = FileModel {
+            schema_version: 0,
+            ..Default::default()
+        };
+        migrate_file_model(&mut model);
+        assert_eq!(model.schema_version, CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn guard_resets_invalid_addrs_and_dedupes_ids() {
+        let servers = vec![
+            make_server("dup", "bad-server", "also-bad"),
+            make_server("dup", "host.example:443", "127.0.0.1:45999"),
+        ];
+        let mut config = config_with(servers, "missing");
+        config.guard();
+
+        // 非法 server 回退到默认值
+        assert_eq!(config.model.servers[0].server, DEFAULT_SERVER_ADDR);
+        // 非法 listen 回退为合法 host:port（端口可能因占用被顺延，故只校验合法性）
+        assert!(parse_host_port(&config.model.servers[0].listen).is_some());
+        assert!(config.model.servers[0].listen.starts_with("127.0.0.1:"));
+        // 重复 ID 被重新生成为唯一值
+        assert_ne!(config.model.servers[0].id, config.model.servers[1].id);
+        // current_server_id 指向不存在的服务器时回退到第一个
+        assert_eq!(config.model.current_server_id, config.model.servers[0].id);
+    }
+}
@@ -6,28 +6,181 @@ pub mod config;
 pub mod sysproxy;
 pub mod autostart;
 pub mod tray;
+pub mod notification;
+pub mod profiles;
+#[cfg(windows)]
+pub mod service;
 
 use tauri::tray::TrayIcon;
 use external_proxy::{ExternalProxyServer, get_output, clear_output};
-use config::Config;
+use config::{Config, ProxyConfig};
 use std::sync::Mutex;
-use tauri::{Manager, State};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
 
 pub struct AppState {
     pub tray: Mutex<Option<TrayIcon>>,
     pub proxy_server: Mutex<Option<ExternalProxyServer>>,
     pub config: tokio::sync::Mutex<Config>,
     pub exiting: std::sync::atomic::AtomicBool,
+    pub health: Mutex<ProxyHealth>,
+    /// 监督任务的代数：每次启动自增，旧的监督任务检测到代数变化后自行退出
+    pub supervisor_gen: AtomicU64,
+    /// 多档案代理注册表：按档案 id 并行管理多个 ech-workers 实例
+    pub profiles: tokio::sync::Mutex<profiles::ProfileRegistry>,
+}
+
+/// 受管代理的健康状态，供前端 `get_proxy_health` 查询。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyHealthStatus {
+    Stopped,
+    Starting,
+    Running,
+    Restarting,
+    Failed,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProxyHealth {
+    pub status: ProxyHealthStatus,
+    pub restart_count: u32,
+}
+
+impl Default for ProxyHealth {
+    fn default() -> Self {
+        Self {
+            status: ProxyHealthStatus::Stopped,
+            restart_count: 0,
+        }
+    }
+}
+
+impl AppState {
+    pub fn set_health_status(&self, status: ProxyHealthStatus) {
+        if let Ok(mut health) = self.health.lock() {
+            health.status = status;
+        }
+    }
+}
+
+// 就绪探测 / 自动重启参数（参考 odproxy 的 wait_for_service / check_service）
+const READINESS_TIMEOUT: Duration = Duration::from_secs(10);
+const READINESS_INTERVAL: Duration = Duration::from_millis(100);
+const SUPERVISOR_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// 轮询 TCP 连接直到监听地址可用，用于确认外部代理已真正开始服务。
+async fn wait_for_listen_ready(listen_addr: &str, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if tokio::net::TcpStream::connect(listen_addr).await.is_ok() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(READINESS_INTERVAL).await;
+    }
+}
+
+/// 创建并启动受管代理，成功后写入 `state.proxy_server`。供 `start_proxy` 与监督任务复用。
+async fn spawn_managed_proxy(state: &AppState, config: ProxyConfig) -> Result<(), String> {
+    let mut server = ExternalProxyServer::new(config)
+        .map_err(|e| format!("Failed to create proxy server: {}", e))?;
+
+    server
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start proxy server: {}", e))?;
+
+    let mut guard = state
+        .proxy_server
+        .lock()
+        .map_err(|_| "Proxy mutex poisoned")?;
+    *guard = Some(server);
+    Ok(())
+}
+
+/// 监督任务（观察者）：受管代理的崩溃自动重启由 `ExternalProxyServer` 内部监督器
+/// 独占负责（见 `ExternalProxyServer::spawn_supervisor`，受 `auto_restart` 开关控制），本任务
+/// 只读取其重启计数并反映到 `ProxyHealth`，并在检测到新的重启或彻底放弃时推送桌面通知。
+/// 这样重启只有一个归属，`get_proxy_health` 的计数也与内部监督器保持一致，不再出现
+/// 两个监督器各自抢着重启、计数彼此背离的竞态。
+pub async fn run_supervisor(app: AppHandle, config: ProxyConfig, generation: u64) {
+    let mut last_reported = 0u32;
+
+    loop {
+        tokio::time::sleep(SUPERVISOR_INTERVAL).await;
+
+        let state = app.state::<AppState>();
+        // 代数变化或正在退出，说明已被新的启动或 stop_proxy 取代，结束本任务
+        if state.supervisor_gen.load(Ordering::SeqCst) != generation
+            || state.exiting.load(std::sync::atomic::Ordering::SeqCst)
+        {
+            return;
+        }
+
+        // 读取受管实例的重启计数；实例已被取走（stop）则结束本任务
+        let restart_count = {
+            let guard = match state.proxy_server.lock() {
+                Ok(g) => g,
+                Err(_) => return,
+            };
+            match guard.as_ref() {
+                Some(server) => server.restart_count(),
+                None => return,
+            }
+        };
+
+        // 内部监督器上报了新的重启：补发崩溃通知并同步健康计数
+        if restart_count > last_reported {
+            for n in (last_reported + 1)..=restart_count {
+                notification::proxy_crashed(n);
+            }
+            last_reported = restart_count;
+            if let Ok(mut health) = state.health.lock() {
+                health.status = ProxyHealthStatus::Restarting;
+                health.restart_count = restart_count;
+            }
+        }
+
+        // 反映当前存活状态：仍在服务则 Running；进程已死且重启次数已达上限说明内部
+        // 监督器已放弃，标记 Failed 并退出
+        let alive =
+            check_external_process_running(&external_proxy::effective_binary_name(&config)).await;
+        if alive {
+            state.set_health_status(ProxyHealthStatus::Running);
+        } else if restart_count >= config.max_restarts {
+            state.set_health_status(ProxyHealthStatus::Failed);
+            return;
+        }
+    }
 }
 
 #[tauri::command]
 async fn start_proxy(
+    app: AppHandle,
     state: State<'_, AppState>,
     config: config::ProxyConfig,
 ) -> Result<String, String> {
+    // 受管实例的子进程已退出（空闲自动停止或内部监督器放弃重启）时先移除旧实例，
+    // 使本次启动重新拉起子进程
+    {
+        let mut guard = state
+            .proxy_server
+            .lock()
+            .map_err(|_| "Proxy mutex poisoned")?;
+        if guard.as_ref().map(|s| !s.is_running()).unwrap_or(false) {
+            *guard = None;
+        }
+    }
 
-    // 如检测到外部进程已在运行，则直接返回成功信息并同步状态
-    if check_external_process_running().await {
+    // 如检测到外部进程已在运行且监听地址可连，则直接返回成功信息并同步状态
+    // （其它档案在别的端口上运行的同名进程不算）
+    if check_external_process_running(&external_proxy::effective_binary_name(&config)).await
+        && external_proxy::is_port_listening(&config.listen_addr).await
+    {
         {
             let mut config_guard = state.config.lock().await;
             let mut last_state = config_guard.get_last_state();
@@ -35,6 +188,7 @@ async fn start_proxy(
             config_guard.set_last_state(last_state);
             let _ = config_guard.save();
         }
+        state.set_health_status(ProxyHealthStatus::Running);
         return Ok("External proxy server already running externally".to_string());
     }
 
@@ -52,23 +206,32 @@ async fn start_proxy(
     }
 
     // ===== 第 2 段：异步启动（无 MutexGuard）=====
-    let mut server = ExternalProxyServer::new(config)
-        .map_err(|e| format!("Failed to create proxy server: {}", e))?;
-
-    server
-        .start()
-        .await
-        .map_err(|e| format!("Failed to start proxy server: {}", e))?;
-
-    // ===== 第 3 段：同步写回（无 await）=====
+    state.set_health_status(ProxyHealthStatus::Starting);
     {
-        let mut guard = state
-            .proxy_server
-            .lock()
-            .map_err(|_| "Proxy mutex poisoned")?;
+        let mut health = state.health.lock().map_err(|_| "Health mutex poisoned")?;
+        health.restart_count = 0;
+    }
+    if let Err(e) = spawn_managed_proxy(&state, config.clone()).await {
+        state.set_health_status(ProxyHealthStatus::Failed);
+        return Err(e);
+    }
 
-        *guard = Some(server);
+    // ===== 第 3 段：就绪探测 —— 只有监听端口真正可连才算启动成功 =====
+    if !wait_for_listen_ready(&config.listen_addr, READINESS_TIMEOUT).await {
+        // 探测失败，回收刚拉起的子进程并报错
+        if let Ok(mut guard) = state.proxy_server.lock() {
+            if let Some(mut server) = guard.take() {
+                let _ = server.stop().await;
+            }
+        }
+        state.set_health_status(ProxyHealthStatus::Failed);
+        return Err(format!(
+            "Proxy did not start listening on {} within {:?}",
+            config.listen_addr, READINESS_TIMEOUT
+        ));
     }
+    state.set_health_status(ProxyHealthStatus::Running);
+    notification::proxy_started(&config.listen_addr);
 
     {
         let mut config_guard = state.config.lock().await;
@@ -78,6 +241,14 @@ async fn start_proxy(
         let _ = config_guard.save();
     }
 
+    // ===== 第 4 段：启动监督（观察）任务：同步重启计数与健康状态，推送崩溃通知 =====
+    let generation = state.supervisor_gen.fetch_add(1, Ordering::SeqCst) + 1;
+    {
+        let app = app.clone();
+        let config = config.clone();
+        tauri::async_runtime::spawn(run_supervisor(app, config, generation));
+    }
+
     Ok("External proxy server started successfully".to_string())
 }
 
@@ -86,6 +257,11 @@ async fn start_proxy(
 async fn stop_proxy(state: State<'_, AppState>) -> Result<String, String> {
     log::info!("stop_proxy called");
 
+    // 使代与健康状态失效，通知监督任务退出
+    state.supervisor_gen.fetch_add(1, Ordering::SeqCst);
+    state.set_health_status(ProxyHealthStatus::Stopped);
+    notification::proxy_stopped();
+
     let mut server = {
         let mut guard = state
             .proxy_server
@@ -139,43 +315,163 @@ async fn stop_proxy(state: State<'_, AppState>) -> Result<String, String> {
     }
 }
 
-/// 检查系统中是否有 ech-workers.exe 进程在运行
-async fn check_external_process_running() -> bool {
-    #[cfg(windows)]
+/// 检查系统中是否有指定二进制的进程在运行（进程枚举，不再解析 tasklist/ps 文本）。
+async fn check_external_process_running(binary_name: &str) -> bool {
+    external_proxy::is_process_running(binary_name)
+}
+
+
+/// 代理运行状态的聚合视图：区分“受管子进程”“外部已在跑”“系统代理开关”等维度。
+#[derive(Clone, serde::Serialize)]
+pub struct ProxyStatusInfo {
+    is_running: bool,
+    is_managed_running: bool,
+    is_external_running: bool,
+    system_proxy_enabled: bool,
+    /// ech-workers 二进制进程是否存在（进程枚举结果）。
+    process_present: bool,
+    /// 监听端口是否可连接（确认确实在提供服务）。
+    port_listening: bool,
+}
+
+#[tauri::command]
+async fn get_proxy_status(state: State<'_, AppState>) -> Result<ProxyStatusInfo, String> {
+    let is_managed_running = {
+        let guard = state
+            .proxy_server
+            .lock()
+            .map_err(|_| "Proxy mutex poisoned")?;
+        guard.is_some()
+    };
+
+    // 进程枚举：系统中是否存在对应二进制进程（支持自定义可执行文件名）
+    let (listen_addr, binary_name) = {
+        let config_guard = state.config.lock().await;
+        let proxy_config = config_guard.get_proxy_config();
+        let binary_name = external_proxy::effective_binary_name(&proxy_config);
+        (proxy_config.listen_addr, binary_name)
+    };
+    let process_present = check_external_process_running(&binary_name).await;
+
+    // 端口探测：确认监听端口确实可连接（区分“进程在但未服务”）
+    let port_listening = external_proxy::is_port_listening(&listen_addr).await;
+
+    let system_proxy_enabled = sysproxy::is_system_proxy_enabled().unwrap_or(false);
+
+    let is_external_running = process_present && port_listening;
+
+    Ok(ProxyStatusInfo {
+        is_running: is_managed_running || is_external_running,
+        is_managed_running,
+        is_external_running,
+        system_proxy_enabled,
+        process_present,
+        port_listening,
+    })
+}
+
+
+/// 判断两份代理配置的连接相关字段是否一致（忽略非连接字段）。
+fn proxy_connection_eq(a: &ProxyConfig, b: &ProxyConfig) -> bool {
+    a.listen_addr == b.listen_addr
+        && a.server_addr == b.server_addr
+        && a.server_ip == b.server_ip
+        && a.token == b.token
+        && a.dns_server == b.dns_server
+        && a.ech_domain == b.ech_domain
+        && a.routing_mode == b.routing_mode
+        && a.upstream_proxy == b.upstream_proxy
+        && a.upstream_proxy_user == b.upstream_proxy_user
+        && a.upstream_proxy_pass == b.upstream_proxy_pass
+}
+
+/// 从磁盘重新加载配置；若运行中的代理连接参数发生变化则以新配置重启，否则仅刷新内存状态。
+async fn reload_and_maybe_restart(
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<ProxyConfig, String> {
     {
-        use std::process::Command;
-        use std::os::windows::process::CommandExt;
+        let mut config_guard = state.config.lock().await;
+        config_guard
+            .reload()
+            .map_err(|e| format!("Failed to reload config: {}", e))?;
+    }
 
-        let output = Command::new("tasklist")
-            .args(&["/FI", "IMAGENAME eq ech-workers.exe", "/FO", "CSV"])
-            .creation_flags(0x08000000)
-            .output();
+    let new_config = {
+        let config_guard = state.config.lock().await;
+        config_guard.get_proxy_config()
+    };
 
-        match output {
-            Ok(result) => {
-                let output_str = String::from_utf8_lossy(&result.stdout);
-                output_str.contains("ech-workers.exe")
-            }
-            Err(_) => false,
+    // 读取运行中代理的启动配置用于对比
+    let running_config = {
+        let guard = state.proxy_server.lock().map_err(|_| "Proxy mutex poisoned")?;
+        guard.as_ref().map(|s| s.config().clone())
+    };
+
+    if let Some(running) = running_config {
+        if !proxy_connection_eq(&running, &new_config) {
+            log::info!("Config changed while proxy running, restarting with new settings");
+            stop_proxy(state_from(app)).await?;
+            start_proxy(app.clone(), state_from(app), new_config.clone()).await?;
         }
     }
-    #[cfg(not(windows))]
-    {
-        false
-    }
+
+    Ok(new_config)
 }
 
+/// 从 `AppHandle` 取回受管的 `AppState`，简化在辅助函数里调用命令。
+fn state_from(app: &AppHandle) -> State<'_, AppState> {
+    app.state::<AppState>()
+}
 
+/// 从分享链接或远程订阅 URL 批量导入服务器。
+/// `input` 以 `http://`/`https://` 开头时视为订阅地址并拉取内容，否则作为粘贴的分享内容直接解析。
 #[tauri::command]
-async fn get_proxy_status(state: State<'_, AppState>) -> Result<bool, String> {
-    let guard = state
-        .proxy_server
-        .lock()
-        .map_err(|_| "Proxy mutex poisoned")?;
+pub async fn import_servers(
+    state: State<'_, AppState>,
+    input: String,
+) -> Result<usize, String> {
+    let input = input.trim().to_string();
+    if input.is_empty() {
+        return Err("Import input is empty".to_string());
+    }
+
+    let payload = if input.starts_with("http://") || input.starts_with("https://") {
+        reqwest::get(&input)
+            .await
+            .map_err(|e| format!("Failed to fetch subscription: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read subscription body: {}", e))?
+    } else {
+        input
+    };
+
+    let added = {
+        let mut config_guard = state.config.lock().await;
+        let added = config_guard.import_servers(&payload);
+        config_guard
+            .save()
+            .map_err(|e| format!("Failed to save config: {}", e))?;
+        added
+    };
+
+    Ok(added)
+}
 
-    Ok(guard.is_some())
+#[tauri::command]
+pub async fn reload_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ProxyConfig, String> {
+    reload_and_maybe_restart(&app, &state).await
 }
 
+#[tauri::command]
+pub async fn get_proxy_health(state: State<'_, AppState>) -> Result<ProxyHealth, String> {
+    let health = state.health.lock().map_err(|_| "Health mutex poisoned")?;
+    Ok(health.clone())
+}
 
 #[tauri::command]
 async fn get_config(state: State<'_, AppState>) -> Result<config::ProxyConfig, String> {
@@ -196,6 +492,79 @@ async fn save_config(
     }
 }
 
+#[tauri::command]
+async fn get_servers(state: State<'_, AppState>) -> Result<Vec<config::Server>, String> {
+    let config_state = state.config.lock().await;
+    Ok(config_state.get_servers())
+}
+
+#[tauri::command]
+async fn get_current_server(state: State<'_, AppState>) -> Result<Option<config::Server>, String> {
+    let config_state = state.config.lock().await;
+    Ok(config_state.get_current_server())
+}
+
+#[tauri::command]
+async fn set_current_server(
+    state: State<'_, AppState>,
+    server_id: String,
+) -> Result<(), String> {
+    let mut config_state = state.config.lock().await;
+    config_state.set_current_server(server_id);
+    match config_state.save() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to save config: {}", e)),
+    }
+}
+
+#[tauri::command]
+async fn upsert_server(
+    state: State<'_, AppState>,
+    server: config::Server,
+) -> Result<(), String> {
+    let mut config_state = state.config.lock().await;
+    config_state.upsert_server(server);
+    match config_state.save() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to save config: {}", e)),
+    }
+}
+
+#[tauri::command]
+async fn delete_server(
+    state: State<'_, AppState>,
+    server_id: String,
+) -> Result<(), String> {
+    let mut config_state = state.config.lock().await;
+    config_state.delete_server(&server_id);
+    match config_state.save() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to save config: {}", e)),
+    }
+}
+
+#[tauri::command]
+async fn get_last_state(state: State<'_, AppState>) -> Result<config::LastState, String> {
+    let config_guard = state.config.lock().await;
+    Ok(config_guard.get_last_state())
+}
+
+#[tauri::command]
+async fn get_autostart_status() -> Result<bool, String> {
+    match autostart::is_autostart_enabled() {
+        Ok(enabled) => Ok(enabled),
+        Err(e) => Err(format!("Failed to check autostart status: {}", e)),
+    }
+}
+
+#[tauri::command]
+async fn get_system_proxy_status(_state: State<'_, AppState>) -> Result<bool, String> {
+    match sysproxy::is_system_proxy_enabled() {
+        Ok(enabled) => Ok(enabled),
+        Err(e) => Err(format!("Failed to check system proxy status: {}", e)),
+    }
+}
+
 #[tauri::command]
 async fn set_system_proxy(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
     if enabled {
@@ -203,6 +572,7 @@ async fn set_system_proxy(enabled: bool, state: State<'_, AppState>) -> Result<(
         let config_state = state.config.lock().await;
         let proxy_config = config_state.get_proxy_config();
         let listen_addr = proxy_config.listen_addr;
+        let bypass = config::effective_bypass(&proxy_config.bypass, proxy_config.bypass_local);
         drop(config_state);
 
         // 解析地址和端口
@@ -214,13 +584,28 @@ async fn set_system_proxy(enabled: bool, state: State<'_, AppState>) -> Result<(
         let host = parts[0];
         let port = parts[1];
 
-        sysproxy::set_system_proxy(host, port)
+        // 首次接管前，快照用户/企业环境原有的系统代理配置，便于关闭时原样还原
+        let already_owned = {
+            let guard = state.config.lock().await;
+            guard.get_last_state().original_proxy.is_some()
+        };
+        let snapshot = if already_owned {
+            None
+        } else {
+            sysproxy::read_proxy_config().ok()
+        };
+
+        sysproxy::set_system_proxy(host, port, &bypass)
             .map_err(|e| format!("Failed to set system proxy: {}", e))?;
 
-        // 保存状态：系统代理已启用
+        // 保存状态：系统代理已启用（固定端点模式）
         let mut config_guard = state.config.lock().await;
         let mut last_state = config_guard.get_last_state();
+        if last_state.original_proxy.is_none() {
+            last_state.original_proxy = snapshot;
+        }
         last_state.system_proxy_enabled = true;
+        last_state.proxy_mode = config::ProxyMode::Fixed;
         config_guard.set_last_state(last_state);
 
         if let Err(e) = config_guard.save() {
@@ -228,14 +613,25 @@ async fn set_system_proxy(enabled: bool, state: State<'_, AppState>) -> Result<(
         }
         drop(config_guard);
     } else {
-        sysproxy::disable_system_proxy()
-            .map_err(|e| format!("Failed to disable system proxy: {}", e))?;
+        // 关闭时优先还原接管前的原始配置，否则直接关闭
+        let original = {
+            let guard = state.config.lock().await;
+            guard.get_last_state().original_proxy
+        };
+        match original {
+            Some(snapshot) => sysproxy::restore_proxy_config(&snapshot)
+                .map_err(|e| format!("Failed to restore system proxy: {}", e))?,
+            None => sysproxy::disable_system_proxy()
+                .map_err(|e| format!("Failed to disable system proxy: {}", e))?,
+        }
 
         // 保存状态：系统代理已禁用
         let mut config_guard = state.config.lock().await;
         let mut last_state = config_guard.get_last_state();
         last_state.system_proxy_enabled = false;
         last_state.was_running = false;
+        last_state.proxy_mode = config::ProxyMode::Off;
+        last_state.original_proxy = None;
         config_guard.set_last_state(last_state);
 
         if let Err(e) = config_guard.save() {
@@ -243,6 +639,43 @@ async fn set_system_proxy(enabled: bool, state: State<'_, AppState>) -> Result<(
         }
         drop(config_guard);
     }
+    notification::system_proxy_toggled(enabled);
+    Ok(())
+}
+
+/// 启用 PAC（自动代理配置）模式，使系统指向指定脚本 URL，并记录到 `LastState` 以便重启恢复。
+#[tauri::command]
+pub async fn set_pac_proxy(url: String, state: State<'_, AppState>) -> Result<(), String> {
+    // 首次接管前快照原始配置
+    let already_owned = {
+        let guard = state.config.lock().await;
+        guard.get_last_state().original_proxy.is_some()
+    };
+    let snapshot = if already_owned {
+        None
+    } else {
+        sysproxy::read_proxy_config().ok()
+    };
+
+    sysproxy::set_auto_proxy(&url)
+        .map_err(|e| format!("Failed to set PAC proxy: {}", e))?;
+
+    let mut config_guard = state.config.lock().await;
+    let mut last_state = config_guard.get_last_state();
+    if last_state.original_proxy.is_none() {
+        last_state.original_proxy = snapshot;
+    }
+    last_state.system_proxy_enabled = true;
+    last_state.proxy_mode = config::ProxyMode::Pac;
+    last_state.pac_url = url;
+    config_guard.set_last_state(last_state);
+
+    if let Err(e) = config_guard.save() {
+        log::error!("Failed to save config after enabling PAC proxy: {}", e);
+    }
+    drop(config_guard);
+
+    notification::system_proxy_toggled(true);
     Ok(())
 }
 
@@ -263,12 +696,70 @@ async fn get_proxy_output() -> Result<Vec<String>, String> {
     Ok(get_output())
 }
 
+#[tauri::command]
+pub async fn get_proxy_output_records() -> Result<Vec<external_proxy::OutputRecord>, String> {
+    Ok(external_proxy::get_output_records())
+}
+
 #[tauri::command]
 async fn clear_proxy_output() -> Result<(), String> {
     clear_output();
     Ok(())
 }
 
+/// 启动指定档案（服务器 id）的代理，使其与其它档案并行运行，各自拥有独立的子进程与输出缓冲。
+#[tauri::command]
+pub async fn start_profile(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let config = {
+        let config_guard = state.config.lock().await;
+        config_guard
+            .get_proxy_config_for(&id)
+            .ok_or_else(|| format!("Unknown profile: {}", id))?
+    };
+
+    let mut registry = state.profiles.lock().await;
+    registry
+        .start(id.clone(), config)
+        .await
+        .map_err(|e| format!("Failed to start profile {}: {}", id, e))?;
+
+    Ok(format!("Profile {} started", id))
+}
+
+/// 停止指定档案的代理，仅终止该档案拥有的子进程。
+#[tauri::command]
+pub async fn stop_profile(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let mut registry = state.profiles.lock().await;
+    registry
+        .stop(&id)
+        .await
+        .map_err(|e| format!("Failed to stop profile {}: {}", id, e))?;
+
+    Ok(format!("Profile {} stopped", id))
+}
+
+/// 返回指定档案当前是否在运行。
+#[tauri::command]
+pub async fn get_profile_status(id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let registry = state.profiles.lock().await;
+    Ok(registry.is_running(&id))
+}
+
+/// 返回当前正在运行的档案 id 列表。
+#[tauri::command]
+pub async fn list_running_profiles(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let registry = state.profiles.lock().await;
+    Ok(registry.running_ids())
+}
+
+/// 返回指定档案的结构化输出记录快照。
+#[tauri::command]
+pub async fn get_profile_output_records(
+    id: String,
+) -> Result<Vec<external_proxy::OutputRecord>, String> {
+    Ok(external_proxy::get_output_records_for(&id))
+}
+
 pub async fn cleanup_all_processes() {
     #[cfg(windows)]
     {
@@ -281,6 +772,60 @@ pub async fn cleanup_all_processes() {
     }
 }
 
+/// 监视 `config.json`，对变更事件做去抖后触发配置热加载。
+pub fn spawn_config_watcher(app: AppHandle) {
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+    let config_file = {
+        let state = app.state::<AppState>();
+        let guard = tauri::async_runtime::block_on(state.config.lock());
+        guard.config_file_path()
+    };
+    let watch_dir = match config_file.parent() {
+        Some(dir) => dir.to_path_buf(),
+        None => {
+            log::warn!("Cannot determine config directory for watcher");
+            return;
+        }
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = match RecommendedWatcher::new(tx, notify::Config::default()) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Failed to create config watcher: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+        log::error!("Failed to watch config directory: {}", e);
+        return;
+    }
+
+    std::thread::spawn(move || {
+        // 持有 watcher 防止被提前 drop
+        let _watcher = watcher;
+        let debounce = Duration::from_millis(500);
+        while let Ok(event) = rx.recv() {
+            let is_relevant = matches!(event, Ok(ref e) if e.paths.iter().any(|p| p == &config_file));
+            if !is_relevant {
+                continue;
+            }
+            // 去抖：在窗口内吞掉后续连续事件
+            while rx.recv_timeout(debounce).is_ok() {}
+
+            log::info!("config.json changed on disk, reloading");
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                if let Err(e) = reload_and_maybe_restart(&app, &state).await {
+                    log::error!("Failed to hot-reload config: {}", e);
+                }
+            });
+        }
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::init();
@@ -296,6 +841,9 @@ pub fn run() {
         proxy_server: Mutex::new(None).into(),
         config: tokio::sync::Mutex::new(config),
         exiting: std::sync::atomic::AtomicBool::new(false),
+        health: Mutex::new(ProxyHealth::default()),
+        supervisor_gen: AtomicU64::new(0),
+        profiles: tokio::sync::Mutex::new(profiles::ProfileRegistry::new()),
     };
 
     tauri::Builder::default()
@@ -305,16 +853,94 @@ pub fn run() {
             start_proxy,
             stop_proxy,
             get_proxy_status,
+            get_proxy_health,
+            reload_config,
+            import_servers,
             get_config,
             save_config,
+            get_servers,
+            get_current_server,
+            set_current_server,
+            upsert_server,
+            delete_server,
+            get_system_proxy_status,
+            get_autostart_status,
+            get_last_state,
             set_system_proxy,
+            set_pac_proxy,
             set_autostart,
             get_proxy_output,
-            clear_proxy_output
+            get_proxy_output_records,
+            clear_proxy_output,
+            start_profile,
+            stop_profile,
+            get_profile_status,
+            list_running_profiles,
+            get_profile_output_records
         ])
         .setup(|app| {
             tray::create_tray(app.app_handle())?;
 
+            // 注册结构化输出事件的推送句柄
+            external_proxy::set_emit_handle(app.app_handle().clone());
+
+            // 桌面通知：注册窗口句柄并根据配置设置开关
+            notification::set_handle(app.app_handle().clone());
+            {
+                let state = app.state::<AppState>();
+                let enabled = tauri::async_runtime::block_on(state.config.lock())
+                    .notifications_enabled();
+                notification::set_enabled(enabled);
+            }
+
+            // 注册 config.json 文件监视器，手动编辑后自动热加载
+            spawn_config_watcher(app.app_handle().clone());
+
+            // 启动后延时恢复上次的系统代理模式（Off / Fixed / Pac）
+            {
+                let app_handle = app.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(3)).await;
+                    let state = app_handle.state::<AppState>();
+                    let (cfg, last_state) = {
+                        let guard = state.config.lock().await;
+                        (guard.get_proxy_config(), guard.get_last_state())
+                    };
+
+                    // 上次运行时代理在跑则自动拉起（外部已有同名进程时跳过）
+                    let binary_name = external_proxy::effective_binary_name(&cfg);
+                    if last_state.was_running
+                        && !check_external_process_running(&binary_name).await
+                    {
+                        if let Err(e) =
+                            start_proxy(app_handle.clone(), state.clone(), cfg).await
+                        {
+                            log::error!("Failed to auto-start proxy on launch: {}", e);
+                        }
+                    }
+
+                    // 按上次使用的系统代理模式恢复（Pac / Fixed）；兼容旧配置
+                    match last_state.proxy_mode {
+                        config::ProxyMode::Pac if !last_state.pac_url.is_empty() => {
+                            if let Err(e) = set_pac_proxy(last_state.pac_url, state).await {
+                                log::warn!("Failed to restore PAC proxy on launch: {}", e);
+                            }
+                        }
+                        config::ProxyMode::Fixed => {
+                            if let Err(e) = set_system_proxy(true, state).await {
+                                log::warn!("Failed to restore system proxy on launch: {}", e);
+                            }
+                        }
+                        config::ProxyMode::Off if last_state.system_proxy_enabled => {
+                            if let Err(e) = set_system_proxy(true, state).await {
+                                log::warn!("Failed to restore system proxy on launch: {}", e);
+                            }
+                        }
+                        _ => {}
+                    }
+                });
+            }
+
             // 设置窗口关闭事件 - 隐藏到托盘而不是退出
             let app_handle = app.app_handle().clone();
             let window = app.get_webview_window("main").unwrap();
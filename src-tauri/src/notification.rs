@@ -0,0 +1,103 @@
+//! 代理状态变更时的桌面通知（基于 `notify-rust`）。
+//!
+//! 通知受配置中的 `notifications` 开关控制。在 Linux 上崩溃/重启通知可点击，点击后经
+//! `get_webview_window("main")` 重新显示并聚焦主窗口；Windows 与 macOS 上为普通通知，
+//! 点击不会显示窗口。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use log::warn;
+use notify_rust::Notification;
+use tauri::{AppHandle, Manager};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+const APP_NAME: &str = "ECH Workers Client";
+
+/// 记录用于点击通知后显示窗口的 AppHandle（在 setup 中调用一次）。
+pub fn set_handle(handle: AppHandle) {
+    let _ = APP_HANDLE.set(handle);
+}
+
+/// 根据配置开关启用/禁用桌面通知。
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+fn show(summary: &str, body: &str) {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return;
+    }
+    if let Err(e) = Notification::new()
+        .appname(APP_NAME)
+        .summary(summary)
+        .body(body)
+        .show()
+    {
+        warn!("Failed to show notification: {}", e);
+    }
+}
+
+pub fn proxy_started(listen_addr: &str) {
+    show("代理已启动", &format!("Proxy started on {}", listen_addr));
+}
+
+pub fn proxy_stopped() {
+    show("代理已停止", "Proxy stopped");
+}
+
+pub fn system_proxy_toggled(enabled: bool) {
+    if enabled {
+        show("系统代理已启用", "System proxy enabled");
+    } else {
+        show("系统代理已禁用", "System proxy disabled");
+    }
+}
+
+/// 崩溃/重启通知。在 Linux 上点击后重新显示并聚焦主窗口；其它平台为不可点击的普通通知。
+pub fn proxy_crashed(attempt: u32) {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return;
+    }
+    let body = format!("Proxy crashed / restarting (attempt {})", attempt);
+
+    // Linux 支持带 action 的交互通知；点击后显示主窗口
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let result = Notification::new()
+            .appname(APP_NAME)
+            .summary("代理已崩溃")
+            .body(&body)
+            .action("default", "显示窗口")
+            .show();
+        match result {
+            Ok(handle) => {
+                std::thread::spawn(move || {
+                    handle.wait_for_action(|action| {
+                        if action == "default" {
+                            show_main_window();
+                        }
+                    });
+                });
+            }
+            Err(e) => warn!("Failed to show crash notification: {}", e),
+        }
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        show("代理已崩溃", &body);
+    }
+}
+
+#[allow(dead_code)]
+fn show_main_window() {
+    if let Some(handle) = APP_HANDLE.get() {
+        if let Some(window) = handle.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! 多档案代理注册表：将档案 id 映射到各自受管的 [`ExternalProxyServer`]。
+//!
+//! 早期设计假定只有一个 `ech-workers` 实例，并按镜像名全量清理，导致无法同时运行
+//! 两个端点。该注册表为每个档案（即配置中的一个服务器）维护独立的子进程、输出缓冲
+//! 与 PID 登记，支持按档案独立启停与状态查询。
+//!
+//! 单实例调用路径仍可经 [`crate::start_proxy`] 使用 `DEFAULT_PROFILE` 档案，两者互不冲突。
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::config::ProxyConfig;
+use crate::external_proxy::ExternalProxyServer;
+
+/// 按档案 id 索引的受管代理集合。
+#[derive(Default)]
+pub struct ProfileRegistry {
+    servers: HashMap<String, ExternalProxyServer>,
+}
+
+impl ProfileRegistry {
+    pub fn new() -> Self {
+        Self {
+            servers: HashMap::new(),
+        }
+    }
+
+    /// 以指定档案 id 启动一个代理；若该档案已在运行则返回错误。
+    pub async fn start(&mut self, id: String, config: ProxyConfig) -> Result<()> {
+        if self.servers.get(&id).map(|s| s.is_running()).unwrap_or(false) {
+            return Err(anyhow!("Profile {} is already running", id));
+        }
+        // 子进程已退出的旧实例（如空闲自动停止）先移除，避免其 Drop 在新实例登记后注销 PID
+        self.servers.remove(&id);
+
+        let mut server = ExternalProxyServer::with_profile(id.clone(), config)?;
+        server.start().await?;
+        self.servers.insert(id, server);
+        Ok(())
+    }
+
+    /// 停止并移除指定档案；该档案未运行时返回错误。
+    pub async fn stop(&mut self, id: &str) -> Result<()> {
+        match self.servers.remove(id) {
+            Some(mut server) => server.stop().await,
+            None => Err(anyhow!("Profile {} is not running", id)),
+        }
+    }
+
+    /// 停止全部档案，供退出/服务停止时统一清理。
+    pub async fn stop_all(&mut self) {
+        for (id, mut server) in self.servers.drain() {
+            if let Err(e) = server.stop().await {
+                log::warn!("Failed to stop profile {}: {}", id, e);
+            }
+        }
+    }
+
+    /// 指定档案当前是否在运行。
+    pub fn is_running(&self, id: &str) -> bool {
+        self.servers.get(id).map(|s| s.is_running()).unwrap_or(false)
+    }
+
+    /// 当前正在运行的档案 id 列表。
+    pub fn running_ids(&self) -> Vec<String> {
+        self.servers
+            .iter()
+            .filter(|(_, s)| s.is_running())
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,177 @@
+//! Windows 服务模式：在无托盘/无 webview 的情况下以系统服务方式运行外部代理。
+//!
+//! 该模块仅在 Windows 上可用，提供 `install-service` / `uninstall-service` 以及
+//! 服务入口。服务运行时直接驱动 [`ExternalProxyServer`]，复用磁盘上的 `ProxyConfig`，
+//! 并把 `SERVICE_CONTROL_STOP` / `SHUTDOWN` 转换为对 `stop()` 和进程清理的调用。
+
+#![cfg(windows)]
+
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use std::ffi::OsString;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::config::Config;
+use crate::external_proxy::ExternalProxyServer;
+
+/// 服务的内部名称（用于 SCM 注册）与显示名称。
+pub const SERVICE_NAME: &str = "ech-workers-client";
+const SERVICE_DISPLAY_NAME: &str = "ECH Workers Client";
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+/// 将当前可执行文件注册为开机自动启动的 Windows 服务。
+pub fn install_service() -> Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+
+    let exe_path = std::env::current_exe()
+        .map_err(|e| anyhow!("Failed to get current exe path: {}", e))?;
+
+    let service_info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: SERVICE_TYPE,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: exe_path,
+        // 以 `--service` 参数启动，main 据此进入服务分发器
+        launch_arguments: vec![OsString::from("--service")],
+        dependencies: vec![],
+        account_name: None, // LocalSystem
+        account_password: None,
+    };
+
+    let service =
+        manager.create_service(&service_info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)?;
+    service.set_description("Manages the ech-workers external proxy as a background service")?;
+    info!("Service '{}' installed", SERVICE_NAME);
+    Ok(())
+}
+
+/// 注销已安装的服务（运行中则先尝试停止）。
+pub fn uninstall_service() -> Result<()> {
+    let manager =
+        ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+
+    let service = manager.open_service(
+        SERVICE_NAME,
+        ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    )?;
+
+    // 运行中则请求停止，等待其退出再删除
+    if let Ok(status) = service.query_status() {
+        if status.current_state != ServiceState::Stopped {
+            let _ = service.stop();
+            for _ in 0..10 {
+                std::thread::sleep(Duration::from_millis(500));
+                if let Ok(s) = service.query_status() {
+                    if s.current_state == ServiceState::Stopped {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    service.delete()?;
+    info!("Service '{}' uninstalled", SERVICE_NAME);
+    Ok(())
+}
+
+/// 进入 SCM 服务分发器；由 `main` 在检测到 `--service` 参数时调用。
+pub fn run() -> Result<()> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .map_err(|e| anyhow!("Failed to start service dispatcher: {}", e))
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("Service exited with error: {}", e);
+    }
+}
+
+fn run_service() -> Result<()> {
+    // 用于把 STOP/SHUTDOWN 控制事件传递给主循环的通道
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
+        match control_event {
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                let _ = shutdown_tx.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)?;
+
+    let running_status = ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state: ServiceState::Running,
+        controls_accepted: ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    };
+    status_handle.set_service_status(running_status)?;
+
+    // 服务模式下直接驱动外部代理，不依赖 webview
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| anyhow!("Failed to create tokio runtime: {}", e))?;
+
+    runtime.block_on(async {
+        let config = Config::load().unwrap_or_else(|e| {
+            error!("Failed to load config in service mode: {}, using defaults", e);
+            Config::default()
+        });
+        let proxy_config = config.get_proxy_config();
+
+        let mut server = match ExternalProxyServer::new(proxy_config) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Failed to create proxy server: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = server.start().await {
+            error!("Failed to start proxy server: {}", e);
+            return;
+        }
+        info!("Service running, external proxy started");
+
+        // 阻塞等待停止信号
+        let _ = shutdown_rx.recv();
+        info!("Service stop requested, shutting down proxy");
+        let _ = server.stop().await;
+        crate::cleanup_all_processes().await;
+    });
+
+    // 上报 STOPPED
+    status_handle.set_service_status(ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state: ServiceState::Stopped,
+        controls_accepted: ServiceControlAccept::empty(),
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    })?;
+
+    Ok(())
+}
@@ -1,161 +1,826 @@
-use anyhow::{Result, anyhow};
-use log::{info, warn};
-use winreg::enums::*;
-use winreg::RegKey;
-
-pub fn set_system_proxy(host: &str, port: &str) -> Result<()> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    
-    // Set proxy server
-    let proxy_settings = hkcu.open_subkey_with_flags(
-        "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings",
-        KEY_ALL_ACCESS
-    ).map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
-
-    let proxy_server = format!("{}:{}", host, port);
-    proxy_settings.set_value("ProxyServer", &proxy_server)
-        .map_err(|e| anyhow!("Failed to set ProxyServer registry value: {}", e))?;
-
-    proxy_settings.set_value("ProxyEnable", &1u32)
-        .map_err(|e| anyhow!("Failed to enable proxy: {}", e))?;
-
-    // Notify system of proxy change
-    unsafe {
-        use windows::Win32::UI::WindowsAndMessaging::SendMessageTimeoutW;
-        use windows::Win32::UI::WindowsAndMessaging::HWND_BROADCAST;
-        use windows::Win32::UI::WindowsAndMessaging::WM_SETTINGCHANGE;
-        use windows::Win32::UI::WindowsAndMessaging::SMTO_NORMAL;
-        use windows::core::HSTRING;
-        use windows::Win32::Foundation::WPARAM;
-        use windows::Win32::Foundation::LPARAM;
-
-        let settings = HSTRING::from("Internet Settings");
-        let result = SendMessageTimeoutW(
-            HWND_BROADCAST,
-            WM_SETTINGCHANGE,
-            WPARAM(0),
-            LPARAM(settings.as_ptr() as isize),
-            SMTO_NORMAL,
-            5000,
-            None,
-        );
-        
-        if result.0 == 0 {
-            warn!("Failed to broadcast proxy settings change");
-        }
-    }
-
-    info!("System proxy set to {}:{}", host, port);
-    Ok(())
+//! 系统代理设置的平台中立封装。
+//!
+//! 对外暴露 [`set_system_proxy`] / [`set_auto_proxy`] / [`disable_system_proxy`]
+//! / [`is_system_proxy_enabled`] / [`get_system_proxy`] / [`read_proxy_config`]
+//! / [`restore_proxy_config`] 等函数，内部按平台分派到各自后端：
+//! - Windows：`Internet Settings` 注册表键 + WinInet 逐连接选项（LAN 与各 RAS/VPN 连接）；
+//! - macOS：`networksetup`，按网络服务读写 Web/Secure/PAC 代理设置；
+//! - Linux：优先 GNOME 的 `gsettings org.gnome.system.proxy`，否则回退到
+//!   `http_proxy`/`https_proxy`/`all_proxy` 环境变量提示。
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 系统代理的完整快照，用于在本应用接管前保存用户/企业环境原有的设置，
+/// 并在关闭代理时原样还原，避免清空注册表键破坏用户的手动配置。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProxySnapshot {
+    pub enabled: bool,
+    pub server: String,
+    pub bypass: String,
+    pub pac_url: String,
+}
+
+/// 启用系统代理，指向 `host:port`。`bypass` 为以 `;` 分隔的绕过列表，空字符串表示不绕过。
+pub fn set_system_proxy(host: &str, port: &str, bypass: &str) -> Result<()> {
+    imp::set_system_proxy(host, port, bypass)
+}
+
+/// 读取当前系统代理的完整配置，作为接管前的快照。
+pub fn read_proxy_config() -> Result<ProxySnapshot> {
+    imp::read_proxy_config()
 }
 
+/// 将之前保存的快照原样写回系统。
+pub fn restore_proxy_config(snapshot: &ProxySnapshot) -> Result<()> {
+    imp::restore_proxy_config(snapshot)
+}
+
+/// 启用 PAC（自动代理配置）模式，让系统指向一个脚本 URL，而非固定端点。
+pub fn set_auto_proxy(pac_url: &str) -> Result<()> {
+    imp::set_auto_proxy(pac_url)
+}
+
+/// 关闭系统代理。
 pub fn disable_system_proxy() -> Result<()> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    
-    let proxy_settings = hkcu.open_subkey_with_flags(
-        "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings",
-        KEY_ALL_ACCESS
-    ).map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
-
-    // 禁用代理
-    proxy_settings.set_value("ProxyEnable", &0u32)
-        .map_err(|e| anyhow!("Failed to disable proxy: {}", e))?;
-
-    // 可选：清除代理服务器设置
-    proxy_settings.delete_value("ProxyServer")
-        .ok(); // 忽略删除失败的错误，因为可能不存在
-
-    // 清除其他可能的代理设置
-    proxy_settings.delete_value("AutoConfigURL")
-        .ok(); // 忽略删除失败的错误
-
-    info!("System proxy disabled via registry");
-
-    // Notify system of proxy change
-    unsafe {
-        use windows::Win32::UI::WindowsAndMessaging::SendMessageTimeoutW;
-        use windows::Win32::UI::WindowsAndMessaging::HWND_BROADCAST;
-        use windows::Win32::UI::WindowsAndMessaging::WM_SETTINGCHANGE;
-        use windows::Win32::UI::WindowsAndMessaging::SMTO_NORMAL;
-        use windows::core::HSTRING;
-        use windows::Win32::Foundation::WPARAM;
-        use windows::Win32::Foundation::LPARAM;
-
-        let settings = HSTRING::from("Internet Settings");
-        let result = SendMessageTimeoutW(
-            HWND_BROADCAST,
-            WM_SETTINGCHANGE,
-            WPARAM(0),
-            LPARAM(settings.as_ptr() as isize),
-            SMTO_NORMAL,
-            5000,
-            None,
-        );
-        
-        if result.0 == 0 {
-            warn!("Failed to broadcast proxy settings change");
-        }
-    }
-
-    // 额外：使用 PowerShell 命令强制禁用系统代理
+    imp::disable_system_proxy()
+}
+
+/// 系统代理当前是否启用。
+#[allow(dead_code)]
+pub fn is_system_proxy_enabled() -> Result<bool> {
+    imp::is_system_proxy_enabled()
+}
+
+/// 读取当前系统代理的 `(host, port)`。
+#[allow(dead_code)]
+pub fn get_system_proxy() -> Result<(String, u16)> {
+    imp::get_system_proxy()
+}
+
+// ===== Windows 后端：Internet Settings 注册表 =====
+#[cfg(windows)]
+mod imp {
+    use anyhow::{anyhow, Result};
+    use log::{info, warn};
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    const INTERNET_SETTINGS: &str =
+        "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings";
+
+    pub fn set_system_proxy(host: &str, port: &str, bypass: &str) -> Result<()> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+        // Set proxy server
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_ALL_ACCESS)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        let proxy_server = format!("{}:{}", host, port);
+        proxy_settings
+            .set_value("ProxyServer", &proxy_server)
+            .map_err(|e| anyhow!("Failed to set ProxyServer registry value: {}", e))?;
+
+        proxy_settings
+            .set_value("ProxyEnable", &1u32)
+            .map_err(|e| anyhow!("Failed to enable proxy: {}", e))?;
+
+        // 绕过列表写入 ProxyOverride（IE/Edge 等读取此值的应用据此直连）
+        if bypass.is_empty() {
+            proxy_settings.delete_value("ProxyOverride").ok();
+        } else {
+            proxy_settings
+                .set_value("ProxyOverride", &bypass.to_string())
+                .map_err(|e| anyhow!("Failed to set ProxyOverride registry value: {}", e))?;
+        }
+
+        // 通过 WinInet 的逐连接选项落地设置，覆盖 LAN 与所有 RAS/VPN 连接
+        apply_connection_proxy(&WinProxy::Fixed {
+            server: &proxy_server,
+            bypass,
+        })?;
+
+        info!("System proxy set to {}:{} (bypass={:?})", host, port, bypass);
+        Ok(())
+    }
+
+    pub fn set_auto_proxy(pac_url: &str) -> Result<()> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_ALL_ACCESS)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        // PAC 模式：写入脚本 URL，清空固定端点
+        proxy_settings
+            .set_value("AutoConfigURL", &pac_url.to_string())
+            .map_err(|e| anyhow!("Failed to set AutoConfigURL registry value: {}", e))?;
+        proxy_settings
+            .set_value("ProxyEnable", &0u32)
+            .map_err(|e| anyhow!("Failed to clear ProxyEnable: {}", e))?;
+        proxy_settings.delete_value("ProxyServer").ok();
+
+        apply_connection_proxy(&WinProxy::Pac { url: pac_url })?;
+
+        info!("System PAC proxy set to {}", pac_url);
+        Ok(())
+    }
+
+    pub fn disable_system_proxy() -> Result<()> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_ALL_ACCESS)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        // 禁用代理
+        proxy_settings
+            .set_value("ProxyEnable", &0u32)
+            .map_err(|e| anyhow!("Failed to disable proxy: {}", e))?;
+
+        // 可选：清除代理服务器设置
+        proxy_settings.delete_value("ProxyServer").ok(); // 忽略删除失败的错误，因为可能不存在
+
+        // 清除其他可能的代理设置
+        proxy_settings.delete_value("AutoConfigURL").ok(); // 忽略删除失败的错误
+
+        info!("System proxy disabled via registry");
+
+        // 逐连接切换为 DIRECT，立即对 LAN 与所有 RAS/VPN 连接生效（不再依赖 PowerShell）
+        apply_connection_proxy(&WinProxy::Direct)?;
+
+        info!("System proxy disabled");
+        Ok(())
+    }
+
+    pub fn is_system_proxy_enabled() -> Result<bool> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_READ)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        match proxy_settings.get_value::<u32, _>("ProxyEnable") {
+            Ok(value) => Ok(value != 0),
+            Err(_) => Ok(false),
+        }
+    }
+
+    pub fn get_system_proxy() -> Result<(String, u16)> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_READ)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        let proxy_server: String = proxy_settings
+            .get_value("ProxyServer")
+            .map_err(|e| anyhow!("Failed to get ProxyServer value: {}", e))?;
+
+        // Parse host:port format
+        if let Some((host, port_str)) = proxy_server.split_once(':') {
+            let port = port_str
+                .parse::<u16>()
+                .map_err(|e| anyhow!("Invalid proxy port: {}", e))?;
+            Ok((host.to_string(), port))
+        } else {
+            Err(anyhow!("Invalid proxy server format: {}", proxy_server))
+        }
+    }
+
+    pub fn read_proxy_config() -> Result<super::ProxySnapshot> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_READ)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        let enabled = proxy_settings
+            .get_value::<u32, _>("ProxyEnable")
+            .map(|v| v != 0)
+            .unwrap_or(false);
+        let server = proxy_settings.get_value::<String, _>("ProxyServer").unwrap_or_default();
+        let bypass = proxy_settings.get_value::<String, _>("ProxyOverride").unwrap_or_default();
+        let pac_url = proxy_settings.get_value::<String, _>("AutoConfigURL").unwrap_or_default();
+
+        Ok(super::ProxySnapshot { enabled, server, bypass, pac_url })
+    }
+
+    pub fn restore_proxy_config(snapshot: &super::ProxySnapshot) -> Result<()> {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        let proxy_settings = hkcu
+            .open_subkey_with_flags(INTERNET_SETTINGS, KEY_ALL_ACCESS)
+            .map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+
+        proxy_settings
+            .set_value("ProxyEnable", &(snapshot.enabled as u32))
+            .map_err(|e| anyhow!("Failed to restore ProxyEnable: {}", e))?;
+
+        if snapshot.server.is_empty() {
+            proxy_settings.delete_value("ProxyServer").ok();
+        } else {
+            proxy_settings.set_value("ProxyServer", &snapshot.server).ok();
+        }
+        if snapshot.bypass.is_empty() {
+            proxy_settings.delete_value("ProxyOverride").ok();
+        } else {
+            proxy_settings.set_value("ProxyOverride", &snapshot.bypass).ok();
+        }
+        if snapshot.pac_url.is_empty() {
+            proxy_settings.delete_value("AutoConfigURL").ok();
+        } else {
+            proxy_settings.set_value("AutoConfigURL", &snapshot.pac_url).ok();
+        }
+
+        // 按快照内容选择逐连接形态落地
+        if !snapshot.pac_url.is_empty() {
+            apply_connection_proxy(&WinProxy::Pac { url: &snapshot.pac_url })?;
+        } else if snapshot.enabled && !snapshot.server.is_empty() {
+            apply_connection_proxy(&WinProxy::Fixed {
+                server: &snapshot.server,
+                bypass: &snapshot.bypass,
+            })?;
+        } else {
+            apply_connection_proxy(&WinProxy::Direct)?;
+        }
+
+        info!("Restored original system proxy configuration");
+        Ok(())
+    }
+
+    /// 一次代理落地请求的三种形态：直连、固定端点、PAC 脚本。
+    enum WinProxy<'a> {
+        Direct,
+        Fixed { server: &'a str, bypass: &'a str },
+        Pac { url: &'a str },
+    }
+
+    /// 通过 WinInet 的逐连接选项（`INTERNET_PER_CONN_OPTION_LISTW`）落地代理设置。
+    ///
+    /// 仅改写注册表里的 `ProxyEnable`/`ProxyServer` 对 LAN 连接生效，但拨号与
+    /// VPN（RAS）连接各有独立的代理配置，必须逐个写入。这里先对 LAN（NULL
+    /// 连接名）应用，再枚举 `RasEnumEntriesW` 返回的每个 RAS 条目逐一应用，
+    /// 最后广播 `INTERNET_OPTION_PROXY_SETTINGS_CHANGED` + `INTERNET_OPTION_REFRESH`
+    /// 让正在运行的进程立即感知。
+    fn apply_connection_proxy(proxy: &WinProxy) -> Result<()> {
+        use windows::core::PWSTR;
+        use windows::Win32::Networking::WinInet::{
+            InternetSetOptionW, INTERNET_OPTION_PER_CONNECTION_OPTION,
+            INTERNET_OPTION_PROXY_SETTINGS_CHANGED, INTERNET_OPTION_REFRESH,
+            INTERNET_PER_CONN_AUTOCONFIG_URL, INTERNET_PER_CONN_FLAGS,
+            INTERNET_PER_CONN_OPTIONW, INTERNET_PER_CONN_OPTIONW_0,
+            INTERNET_PER_CONN_OPTION_LISTW, INTERNET_PER_CONN_PROXY_BYPASS,
+            INTERNET_PER_CONN_PROXY_SERVER, PROXY_TYPE_AUTO_PROXY_URL, PROXY_TYPE_DIRECT,
+            PROXY_TYPE_PROXY,
+        };
+
+        // 将 Rust 字符串转为以 NUL 结尾的 UTF-16 缓冲区，缓冲区需活到选项应用完毕。
+        fn wide(s: &str) -> Vec<u16> {
+            s.encode_utf16().chain(std::iter::once(0)).collect()
+        }
+
+        // 为给定连接名应用一组逐连接选项；connection 为 None 表示 LAN。
+        unsafe fn apply(proxy: &WinProxy, connection: Option<&mut [u16]>) -> bool {
+            let mut options: Vec<INTERNET_PER_CONN_OPTIONW> = Vec::new();
+
+            // 各缓冲区需在 InternetSetOptionW 调用期间保持存活。
+            let mut server_buf;
+            let mut bypass_buf;
+            let mut url_buf;
+
+            let flags = match proxy {
+                WinProxy::Direct => INTERNET_PER_CONN_OPTIONW {
+                    dwOption: INTERNET_PER_CONN_FLAGS,
+                    Value: INTERNET_PER_CONN_OPTIONW_0 {
+                        dwValue: PROXY_TYPE_DIRECT.0 as u32,
+                    },
+                },
+                WinProxy::Fixed { server, bypass } => {
+                    server_buf = wide(server);
+                    bypass_buf = wide(bypass);
+                    options.push(INTERNET_PER_CONN_OPTIONW {
+                        dwOption: INTERNET_PER_CONN_PROXY_SERVER,
+                        Value: INTERNET_PER_CONN_OPTIONW_0 {
+                            pszValue: PWSTR(server_buf.as_mut_ptr()),
+                        },
+                    });
+                    options.push(INTERNET_PER_CONN_OPTIONW {
+                        dwOption: INTERNET_PER_CONN_PROXY_BYPASS,
+                        Value: INTERNET_PER_CONN_OPTIONW_0 {
+                            pszValue: PWSTR(bypass_buf.as_mut_ptr()),
+                        },
+                    });
+                    INTERNET_PER_CONN_OPTIONW {
+                        dwOption: INTERNET_PER_CONN_FLAGS,
+                        Value: INTERNET_PER_CONN_OPTIONW_0 {
+                            dwValue: (PROXY_TYPE_PROXY.0 | PROXY_TYPE_DIRECT.0) as u32,
+                        },
+                    }
+                }
+                WinProxy::Pac { url } => {
+                    url_buf = wide(url);
+                    options.push(INTERNET_PER_CONN_OPTIONW {
+                        dwOption: INTERNET_PER_CONN_AUTOCONFIG_URL,
+                        Value: INTERNET_PER_CONN_OPTIONW_0 {
+                            pszValue: PWSTR(url_buf.as_mut_ptr()),
+                        },
+                    });
+                    INTERNET_PER_CONN_OPTIONW {
+                        dwOption: INTERNET_PER_CONN_FLAGS,
+                        Value: INTERNET_PER_CONN_OPTIONW_0 {
+                            dwValue: (PROXY_TYPE_AUTO_PROXY_URL.0 | PROXY_TYPE_DIRECT.0) as u32,
+                        },
+                    }
+                }
+            };
+            options.push(flags);
+
+            let conn_ptr = match connection {
+                Some(name) => PWSTR(name.as_mut_ptr()),
+                None => PWSTR::null(),
+            };
+            let mut list = INTERNET_PER_CONN_OPTION_LISTW {
+                dwSize: std::mem::size_of::<INTERNET_PER_CONN_OPTION_LISTW>() as u32,
+                pszConnection: conn_ptr,
+                dwOptionCount: options.len() as u32,
+                dwOptionError: 0,
+                pOptions: options.as_mut_ptr(),
+            };
+
+            InternetSetOptionW(
+                None,
+                INTERNET_OPTION_PER_CONNECTION_OPTION,
+                Some(&mut list as *mut _ as *const std::ffi::c_void),
+                list.dwSize,
+            )
+            .is_ok()
+        }
+
+        unsafe {
+            // LAN 连接
+            if !apply(proxy, None) {
+                warn!("InternetSetOptionW failed for LAN connection");
+            }
+
+            // 各 RAS/VPN 连接
+            for mut entry in ras_entries() {
+                if !apply(proxy, Some(entry.as_mut_slice())) {
+                    warn!("InternetSetOptionW failed for a RAS connection");
+                }
+            }
+
+            // 通知正在运行的进程刷新代理
+            let _ = InternetSetOptionW(None, INTERNET_OPTION_PROXY_SETTINGS_CHANGED, None, 0);
+            let _ = InternetSetOptionW(None, INTERNET_OPTION_REFRESH, None, 0);
+        }
+
+        Ok(())
+    }
+
+    /// 枚举所有 RAS（拨号/VPN）连接项名，返回各自以 NUL 结尾的 UTF-16 缓冲区。
+    fn ras_entries() -> Vec<Vec<u16>> {
+        use windows::Win32::Foundation::ERROR_BUFFER_TOO_SMALL;
+        use windows::Win32::NetworkManagement::Ras::{RasEnumEntriesW, RASENTRYNAMEW};
+
+        let mut names = Vec::new();
+        unsafe {
+            let entry_size = std::mem::size_of::<RASENTRYNAMEW>() as u32;
+            let mut buf: Vec<RASENTRYNAMEW> = vec![RASENTRYNAMEW::default(); 1];
+            buf[0].dwSize = entry_size;
+            let mut cb = entry_size;
+            let mut count = 0u32;
+
+            let ret = RasEnumEntriesW(None, None, Some(buf.as_mut_ptr()), &mut cb, &mut count);
+            if ret == ERROR_BUFFER_TOO_SMALL.0 {
+                let needed = (cb / entry_size).max(1) as usize;
+                buf = vec![RASENTRYNAMEW::default(); needed];
+                for e in buf.iter_mut() {
+                    e.dwSize = entry_size;
+                }
+                let ret =
+                    RasEnumEntriesW(None, None, Some(buf.as_mut_ptr()), &mut cb, &mut count);
+                if ret != 0 {
+                    return names;
+                }
+            } else if ret != 0 {
+                return names;
+            }
+
+            for entry in buf.iter().take(count as usize) {
+                let raw = &entry.szEntryName;
+                let len = raw.iter().position(|&c| c == 0).unwrap_or(raw.len());
+                let mut name: Vec<u16> = raw[..len].to_vec();
+                name.push(0);
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+// ===== macOS 后端：networksetup（写入 SystemConfiguration 代理键）=====
+#[cfg(target_os = "macos")]
+mod imp {
+    use anyhow::{anyhow, Result};
+    use log::{info, warn};
     use std::process::Command;
-    use std::os::windows::process::CommandExt;
-    
-    let powershell_result = Command::new("powershell")
-        .args(&["-Command", "Set-ItemProperty -Path 'HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings' -Name ProxyEnable -Value 0"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output();
 
-    match powershell_result {
-        Ok(output) => {
-            if !output.status.success() {
-                warn!("PowerShell proxy disable command failed: {}", String::from_utf8_lossy(&output.stderr));
-            } else {
-                info!("PowerShell proxy disable command executed successfully");
+    /// 枚举所有启用的网络服务（过滤被禁用的 `*` 前缀项）。
+    fn network_services() -> Result<Vec<String>> {
+        let output = Command::new("networksetup")
+            .arg("-listallnetworkservices")
+            .output()
+            .map_err(|e| anyhow!("Failed to run networksetup: {}", e))?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .skip(1) // 首行为说明文字
+            .filter(|l| !l.starts_with('*')) // `*` 前缀表示该服务已被禁用
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    }
+
+    /// 运行一次 `networksetup`，既检查能否启动子进程，也检查退出码——否则设置会被
+    /// 静默吞掉，调用方却收到 `Ok(())`。
+    fn run_networksetup(args: &[&str]) -> Result<()> {
+        let status = Command::new("networksetup")
+            .args(args)
+            .status()
+            .map_err(|e| anyhow!("Failed to run networksetup {:?}: {}", args, e))?;
+        if !status.success() {
+            return Err(anyhow!("networksetup {:?} exited with {}", args, status));
+        }
+        Ok(())
+    }
+
+    pub fn set_system_proxy(host: &str, port: &str, bypass: &str) -> Result<()> {
+        let services = network_services()?;
+        // 绕过域名按 `;` 拆分后传给 -setproxybypassdomains
+        let bypass_domains: Vec<&str> = bypass
+            .split(';')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let mut applied = 0usize;
+        let mut last_err: Option<anyhow::Error> = None;
+        for service in &services {
+            let res = (|| -> Result<()> {
+                run_networksetup(&["-setwebproxy", service, host, port])?;
+                run_networksetup(&["-setsecurewebproxy", service, host, port])?;
+                run_networksetup(&["-setwebproxystate", service, "on"])?;
+                run_networksetup(&["-setsecurewebproxystate", service, "on"])?;
+                if !bypass_domains.is_empty() {
+                    let mut args = vec!["-setproxybypassdomains", service.as_str()];
+                    args.extend(bypass_domains.iter().copied());
+                    run_networksetup(&args)?;
+                }
+                Ok(())
+            })();
+            match res {
+                Ok(()) => applied += 1,
+                Err(e) => {
+                    warn!("Failed to set system proxy on {}: {}", service, e);
+                    last_err = Some(e);
+                }
             }
         }
-        Err(e) => {
-            warn!("Failed to execute PowerShell proxy disable command: {}", e);
+        if applied == 0 {
+            return Err(last_err
+                .unwrap_or_else(|| anyhow!("No network service accepted the system proxy setting")));
         }
+        info!("System proxy set to {}:{} on {}/{} service(s)", host, port, applied, services.len());
+        Ok(())
     }
 
-    info!("System proxy disabled");
-    Ok(())
-}
+    pub fn set_auto_proxy(pac_url: &str) -> Result<()> {
+        let services = network_services()?;
+        let mut applied = 0usize;
+        let mut last_err: Option<anyhow::Error> = None;
+        for service in &services {
+            let res = (|| -> Result<()> {
+                run_networksetup(&["-setautoproxyurl", service, pac_url])?;
+                run_networksetup(&["-setautoproxystate", service, "on"])?;
+                // PAC 模式下关闭固定端点
+                run_networksetup(&["-setwebproxystate", service, "off"])?;
+                run_networksetup(&["-setsecurewebproxystate", service, "off"])?;
+                Ok(())
+            })();
+            match res {
+                Ok(()) => applied += 1,
+                Err(e) => {
+                    warn!("Failed to set PAC proxy on {}: {}", service, e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        if applied == 0 {
+            return Err(last_err
+                .unwrap_or_else(|| anyhow!("No network service accepted the PAC proxy setting")));
+        }
+        info!("System PAC proxy set to {} on {}/{} service(s)", pac_url, applied, services.len());
+        Ok(())
+    }
 
-#[allow(dead_code)]
-pub fn is_system_proxy_enabled() -> Result<bool> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    
-    let proxy_settings = hkcu.open_subkey_with_flags(
-        "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings",
-        KEY_READ
-    ).map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
+    pub fn disable_system_proxy() -> Result<()> {
+        let services = network_services()?;
+        let mut applied = 0usize;
+        let mut last_err: Option<anyhow::Error> = None;
+        for service in &services {
+            let res = (|| -> Result<()> {
+                run_networksetup(&["-setautoproxystate", service, "off"])?;
+                run_networksetup(&["-setwebproxystate", service, "off"])?;
+                run_networksetup(&["-setsecurewebproxystate", service, "off"])?;
+                run_networksetup(&["-setsocksfirewallproxystate", service, "off"])?;
+                Ok(())
+            })();
+            match res {
+                Ok(()) => applied += 1,
+                Err(e) => {
+                    warn!("Failed to disable system proxy on {}: {}", service, e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        if applied == 0 {
+            return Err(last_err
+                .unwrap_or_else(|| anyhow!("No network service accepted disabling the system proxy")));
+        }
+        info!("System proxy disabled on {}/{} service(s)", applied, services.len());
+        Ok(())
+    }
 
-    match proxy_settings.get_value::<u32, _>("ProxyEnable") {
-        Ok(value) => Ok(value != 0),
-        Err(_) => Ok(false),
+    pub fn is_system_proxy_enabled() -> Result<bool> {
+        for service in network_services()? {
+            let output = Command::new("networksetup")
+                .args(["-getwebproxy", &service])
+                .output()
+                .map_err(|e| anyhow!("Failed to read proxy state: {}", e))?;
+            let text = String::from_utf8_lossy(&output.stdout);
+            if text.lines().any(|l| l.trim() == "Enabled: Yes") {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn get_system_proxy() -> Result<(String, u16)> {
+        for service in network_services()? {
+            let output = Command::new("networksetup")
+                .args(["-getwebproxy", &service])
+                .output()
+                .map_err(|e| anyhow!("Failed to read proxy: {}", e))?;
+            let text = String::from_utf8_lossy(&output.stdout);
+            let mut host = String::new();
+            let mut port = 0u16;
+            for line in text.lines() {
+                if let Some(v) = line.strip_prefix("Server:") {
+                    host = v.trim().to_string();
+                } else if let Some(v) = line.strip_prefix("Port:") {
+                    port = v.trim().parse().unwrap_or(0);
+                }
+            }
+            if !host.is_empty() && port != 0 {
+                return Ok((host, port));
+            }
+        }
+        Err(anyhow!("No active system proxy configured"))
+    }
+
+    pub fn read_proxy_config() -> Result<super::ProxySnapshot> {
+        let enabled = is_system_proxy_enabled().unwrap_or(false);
+        let server = get_system_proxy()
+            .map(|(h, p)| format!("{}:{}", h, p))
+            .unwrap_or_default();
+
+        // 捕获绕过域名与 PAC URL 记入快照；本后端的 restore_proxy_config 只按
+        // enabled/server/bypass 还原固定端点或关闭代理，不会写回 PAC URL
+        let mut bypass = String::new();
+        let mut pac_url = String::new();
+        if let Some(service) = network_services().ok().and_then(|s| s.into_iter().next()) {
+            if let Ok(out) = Command::new("networksetup")
+                .args(["-getproxybypassdomains", &service])
+                .output()
+            {
+                let text = String::from_utf8_lossy(&out.stdout);
+                let domains: Vec<&str> = text
+                    .lines()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty() && !l.starts_with("There aren't any"))
+                    .collect();
+                bypass = domains.join(";");
+            }
+            if let Ok(out) = Command::new("networksetup")
+                .args(["-getautoproxyurl", &service])
+                .output()
+            {
+                let text = String::from_utf8_lossy(&out.stdout);
+                for line in text.lines() {
+                    if let Some(v) = line.strip_prefix("URL:") {
+                        let v = v.trim();
+                        if !v.is_empty() && v != "(null)" {
+                            pac_url = v.to_string();
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(super::ProxySnapshot {
+            enabled,
+            server,
+            bypass,
+            pac_url,
+        })
+    }
+
+    pub fn restore_proxy_config(snapshot: &super::ProxySnapshot) -> Result<()> {
+        if snapshot.enabled {
+            if let Some((host, port)) = snapshot.server.rsplit_once(':') {
+                return set_system_proxy(host, port, &snapshot.bypass);
+            }
+        }
+        disable_system_proxy()
     }
 }
 
-#[allow(dead_code)]
-pub fn get_system_proxy() -> Result<(String, u16)> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    
-    let proxy_settings = hkcu.open_subkey_with_flags(
-        "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings",
-        KEY_READ
-    ).map_err(|e| anyhow!("Failed to open Internet Settings registry key: {}", e))?;
-
-    let proxy_server: String = proxy_settings.get_value("ProxyServer")
-        .map_err(|e| anyhow!("Failed to get ProxyServer value: {}", e))?;
-
-    // Parse host:port format
-    if let Some((host, port_str)) = proxy_server.split_once(':') {
-        let port = port_str.parse::<u16>()
-            .map_err(|e| anyhow!("Invalid proxy port: {}", e))?;
-        Ok((host.to_string(), port))
-    } else {
-        Err(anyhow!("Invalid proxy server format: {}", proxy_server))
+// ===== Linux 后端：GNOME gsettings，回退到环境变量提示 =====
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use anyhow::{anyhow, Result};
+    use log::info;
+    use std::process::Command;
+
+    const GNOME_SCHEMA: &str = "org.gnome.system.proxy";
+
+    /// 系统是否可用 `gsettings`（GNOME 桌面）。
+    fn has_gsettings() -> bool {
+        Command::new("gsettings")
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    /// 写入一个 gsettings 键，检查退出码，使失败的设置不会被当作成功返回。
+    fn gset(path: &str, key: &str, value: &str) -> Result<()> {
+        let status = Command::new("gsettings")
+            .args(["set", path, key, value])
+            .status()
+            .map_err(|e| anyhow!("Failed to run gsettings set {} {}: {}", path, key, e))?;
+        if !status.success() {
+            return Err(anyhow!("gsettings set {} {} exited with {}", path, key, status));
+        }
+        Ok(())
+    }
+
+    pub fn set_system_proxy(host: &str, port: &str, bypass: &str) -> Result<()> {
+        if has_gsettings() {
+            gset(GNOME_SCHEMA, "mode", "manual")?;
+            for proto in ["http", "https"] {
+                gset(&format!("{}.{}", GNOME_SCHEMA, proto), "host", host)?;
+                gset(&format!("{}.{}", GNOME_SCHEMA, proto), "port", port)?;
+            }
+            gset(&format!("{}.socks", GNOME_SCHEMA), "host", host)?;
+            gset(&format!("{}.socks", GNOME_SCHEMA), "port", port)?;
+            // 绕过列表写入 ignore-hosts（GVariant 字符串数组字面量）
+            let hosts: Vec<String> = bypass
+                .split(';')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| format!("'{}'", s))
+                .collect();
+            if !hosts.is_empty() {
+                gset(GNOME_SCHEMA, "ignore-hosts", &format!("[{}]", hosts.join(", ")))?;
+            }
+            info!("System proxy set to {}:{} via gsettings", host, port);
+        } else {
+            // 无 GNOME 时无法全局设置；提示用户导出代理环境变量
+            info!(
+                "gsettings unavailable; export http_proxy=http://{host}:{port} \
+                 https_proxy=http://{host}:{port} all_proxy=socks5://{host}:{port}",
+                host = host,
+                port = port
+            );
+        }
+        Ok(())
+    }
+
+    pub fn set_auto_proxy(pac_url: &str) -> Result<()> {
+        if has_gsettings() {
+            gset(GNOME_SCHEMA, "autoconfig-url", pac_url)?;
+            gset(GNOME_SCHEMA, "mode", "auto")?;
+            info!("System PAC proxy set to {} via gsettings", pac_url);
+        } else {
+            info!("gsettings unavailable; set auto_proxy_url manually to {}", pac_url);
+        }
+        Ok(())
+    }
+
+    pub fn disable_system_proxy() -> Result<()> {
+        if has_gsettings() {
+            gset(GNOME_SCHEMA, "mode", "none")?;
+            info!("System proxy disabled via gsettings");
+        } else {
+            info!("gsettings unavailable; unset http_proxy/https_proxy/all_proxy to disable");
+        }
+        Ok(())
+    }
+
+    pub fn is_system_proxy_enabled() -> Result<bool> {
+        if has_gsettings() {
+            let output = Command::new("gsettings")
+                .args(["get", GNOME_SCHEMA, "mode"])
+                .output()
+                .map_err(|e| anyhow!("Failed to read gsettings proxy mode: {}", e))?;
+            let mode = String::from_utf8_lossy(&output.stdout);
+            Ok(mode.contains("manual") || mode.contains("auto"))
+        } else {
+            // 回退：检测是否设置了代理环境变量
+            Ok(std::env::var("http_proxy").is_ok() || std::env::var("HTTP_PROXY").is_ok())
+        }
+    }
+
+    pub fn get_system_proxy() -> Result<(String, u16)> {
+        if has_gsettings() {
+            let schema = format!("{}.http", GNOME_SCHEMA);
+            let host_out = Command::new("gsettings")
+                .args(["get", &schema, "host"])
+                .output()
+                .map_err(|e| anyhow!("Failed to read proxy host: {}", e))?;
+            let port_out = Command::new("gsettings")
+                .args(["get", &schema, "port"])
+                .output()
+                .map_err(|e| anyhow!("Failed to read proxy port: {}", e))?;
+            let host = String::from_utf8_lossy(&host_out.stdout)
+                .trim()
+                .trim_matches('\'')
+                .to_string();
+            let port = String::from_utf8_lossy(&port_out.stdout)
+                .trim()
+                .parse::<u16>()
+                .unwrap_or(0);
+            if !host.is_empty() && port != 0 {
+                return Ok((host, port));
+            }
+        }
+        Err(anyhow!("No active system proxy configured"))
+    }
+
+    pub fn read_proxy_config() -> Result<super::ProxySnapshot> {
+        let enabled = is_system_proxy_enabled().unwrap_or(false);
+        let server = get_system_proxy()
+            .map(|(h, p)| format!("{}:{}", h, p))
+            .unwrap_or_default();
+
+        // 捕获绕过列表与 PAC URL 记入快照；本后端的 restore_proxy_config 只按
+        // enabled/server/bypass 还原固定端点或关闭代理，不会写回 PAC URL
+        let mut bypass = String::new();
+        let mut pac_url = String::new();
+        if has_gsettings() {
+            if let Ok(out) = Command::new("gsettings")
+                .args(["get", GNOME_SCHEMA, "ignore-hosts"])
+                .output()
+            {
+                // 形如 ['localhost', '127.0.0.0/8'] 的 GVariant 字符串数组
+                let text = String::from_utf8_lossy(&out.stdout);
+                let hosts: Vec<String> = text
+                    .trim()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .split(',')
+                    .map(|s| s.trim().trim_matches('\''))
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+                    .collect();
+                bypass = hosts.join(";");
+            }
+            if let Ok(out) = Command::new("gsettings")
+                .args(["get", GNOME_SCHEMA, "autoconfig-url"])
+                .output()
+            {
+                let text = String::from_utf8_lossy(&out.stdout);
+                let url = text.trim().trim_matches('\'');
+                if !url.is_empty() {
+                    pac_url = url.to_string();
+                }
+            }
+        }
+
+        Ok(super::ProxySnapshot {
+            enabled,
+            server,
+            bypass,
+            pac_url,
+        })
+    }
+
+    pub fn restore_proxy_config(snapshot: &super::ProxySnapshot) -> Result<()> {
+        if snapshot.enabled {
+            if let Some((host, port)) = snapshot.server.rsplit_once(':') {
+                return set_system_proxy(host, port, &snapshot.bypass);
+            }
+        }
+        disable_system_proxy()
     }
 }
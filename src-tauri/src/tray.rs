@@ -1,9 +1,12 @@
 use tauri::{
     AppHandle, Manager, WebviewWindow,
-    menu::{Menu, MenuItem, PredefinedMenuItem, CheckMenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem, CheckMenuItem, Submenu},
     tray::{TrayIconBuilder, TrayIconEvent, MouseButton},
 };
 
+/// 托盘档案菜单项 id 的前缀；其后拼接服务器（档案）id。
+const PROFILE_MENU_PREFIX: &str = "profile:";
+
 pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     // 创建托盘菜单
     let start_proxy_item = MenuItem::new(app, "启动代理", true, Some("start_proxy"))?;
@@ -13,10 +16,42 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let separator = PredefinedMenuItem::separator(app)?;
     let quit_item = MenuItem::new(app, "退出", true, Some("quit"))?;
 
+    // 多档案子菜单：每个已配置的服务器对应一个可独立启停的勾选项
+    let servers = {
+        let state = app.state::<crate::AppState>();
+        let guard = tauri::async_runtime::block_on(state.config.lock());
+        guard.get_servers()
+    };
+    let running_ids: std::collections::HashSet<String> = {
+        let state = app.state::<crate::AppState>();
+        let guard = tauri::async_runtime::block_on(state.profiles.lock());
+        guard.running_ids().into_iter().collect()
+    };
+    let mut profile_items: Vec<CheckMenuItem<tauri::Wry>> = Vec::new();
+    for server in &servers {
+        let label = if server.name.is_empty() {
+            server.server.clone()
+        } else {
+            server.name.clone()
+        };
+        let item = CheckMenuItem::new(
+            app,
+            label,
+            running_ids.contains(&server.id),
+            true,
+            Some(format!("{}{}", PROFILE_MENU_PREFIX, server.id)),
+        )?;
+        profile_items.push(item);
+    }
+    let profile_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> =
+        profile_items.iter().map(|i| i as &dyn tauri::menu::IsMenuItem<tauri::Wry>).collect();
+    let profiles_submenu = Submenu::with_items(app, "代理档案", true, &profile_refs)?;
+
     let menu = Menu::with_items(app, &[
         &start_proxy_item,
         &stop_proxy_item,
         &toggle_system_proxy_item,
+        &profiles_submenu,
         &separator,
         &quit_item,
     ])?;
@@ -33,6 +68,12 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         let start_item = start_proxy_item.clone();
         let stop_item = stop_proxy_item.clone();
         let sys_item = toggle_system_proxy_item.clone();
+        // 档案勾选项按其档案 id 配对，供状态轮询刷新运行状态
+        let profile_pairs: Vec<(String, CheckMenuItem<tauri::Wry>)> = servers
+            .iter()
+            .map(|s| s.id.clone())
+            .zip(profile_items.iter().cloned())
+            .collect();
         let handle = app.app_handle().clone();
         tauri::async_runtime::spawn(async move {
             loop {
@@ -48,6 +89,15 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                 let _ = stop_item.set_enabled(is_managed_running);
                 let sys_enabled = crate::sysproxy::is_system_proxy_enabled().unwrap_or(false);
                 let _ = sys_item.set_checked(sys_enabled);
+
+                // 刷新每个档案的运行状态勾选
+                let running: std::collections::HashSet<String> = {
+                    let guard = state.profiles.lock().await;
+                    guard.running_ids().into_iter().collect()
+                };
+                for (id, item) in &profile_pairs {
+                    let _ = item.set_checked(running.contains(id));
+                }
                 tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
             }
         });
@@ -88,7 +138,7 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                         let config_guard = state.config.lock().await;
                         config_guard.get_proxy_config()
                     };
-                    let _ = crate::start_proxy(state, cfg).await;
+                    let _ = crate::start_proxy(handle.clone(), state, cfg).await;
                     let _ = stop_item.set_enabled(true);
                     let _ = start_item.set_enabled(false);
                 });
@@ -117,6 +167,24 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                     let _ = sys_item.set_checked(target);
                 });
             }
+            id if id.starts_with(PROFILE_MENU_PREFIX) => {
+                // 档案级独立启停：根据注册表当前状态取反
+                let profile_id = id[PROFILE_MENU_PREFIX.len()..].to_string();
+                log::info!("Toggle profile {} requested from tray menu", profile_id);
+                let handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let state = handle.state::<crate::AppState>();
+                    let running = {
+                        let guard = state.profiles.lock().await;
+                        guard.is_running(&profile_id)
+                    };
+                    if running {
+                        let _ = crate::stop_profile(profile_id, state).await;
+                    } else {
+                        let _ = crate::start_profile(profile_id, state).await;
+                    }
+                });
+            }
             "quit" => {
                 log::info!("=== Quit menu item clicked ===");
                 let handle = app_handle.clone();
@@ -134,6 +202,8 @@ pub fn create_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                     let state = handle_clone.state::<crate::AppState>();
                     log::info!("Stopping proxy...");
                     let _ = crate::stop_proxy(state.clone()).await;
+                    log::info!("Stopping all proxy profiles...");
+                    state.profiles.lock().await.stop_all().await;
                     log::info!("Disabling system proxy...");
                     let _ = crate::set_system_proxy(false, state.clone()).await;
                     log::info!("Cleaning up processes...");